@@ -1,3 +1,4 @@
+mod packets;
 mod registry;
 
 use std::io::Write;
@@ -8,10 +9,15 @@ use proc_macro2::TokenStream;
 
 pub const OUT_DIR: &str = "src/generated";
 
+type BuildFunction = (fn() -> TokenStream, &'static str);
+
 pub fn main() {
     std::fs::create_dir_all(OUT_DIR).unwrap();
 
-    let build_functions: Vec<(fn() -> TokenStream, &str)> = vec![(registry::build, "registry.rs")];
+    let build_functions: Vec<BuildFunction> = vec![
+        (packets::build, "packets.rs"),
+        (registry::build, "registry.rs"),
+    ];
 
     build_functions.iter().for_each(|(build_fn, file)| {
         let raw_code = build_fn().to_string();
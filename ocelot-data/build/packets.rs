@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use serde_json::Value;
+
+type PacketReport = BTreeMap<String, BTreeMap<String, BTreeMap<String, Value>>>;
+
+pub fn build() -> TokenStream {
+    println!("cargo:rerun-if-changed=../assets/packets.json");
+
+    let json_str =
+        std::fs::read_to_string("../assets/packets.json").expect("Failed to read packets.json");
+    let data: PacketReport = serde_json::from_str(&json_str).expect("Failed to parse packets.json");
+
+    let states = data.iter().map(|(state, directions)| {
+        let state = Ident::new(state, Span::call_site());
+        let directions = directions.iter().map(|(direction, packets)| {
+            let direction = Ident::new(direction, Span::call_site());
+            let mut packets: Vec<(&String, i32)> = packets
+                .iter()
+                .map(|(name, packet)| {
+                    let id = packet["protocol_id"]
+                        .as_i64()
+                        .unwrap_or_else(|| panic!("{} has no protocol_id!", name));
+                    (name, id as i32)
+                })
+                .collect();
+            packets.sort_by_key(|(_, id)| *id);
+            let constants = packets.iter().map(|(name, id)| {
+                let path = name.strip_prefix("minecraft:").unwrap_or(name);
+                let constant = Ident::new(&path.to_uppercase(), Span::call_site());
+                quote! {
+                    pub const #constant: i32 = #id;
+                }
+            });
+            let names = packets.iter().map(|(name, _)| name);
+            let ids = packets.iter().map(|(_, id)| id);
+            quote! {
+                pub mod #direction {
+                    #(#constants)*
+
+                    pub const PACKETS: &[(&str, i32)] = &[
+                        #((#names, #ids)),*
+                    ];
+
+                    /// ID of the packet with the given resource name, usable in const contexts.
+                    pub const fn id(name: &str) -> Option<i32> {
+                        let mut i = 0;
+                        while i < PACKETS.len() {
+                            if super::super::str_eq(PACKETS[i].0, name) {
+                                return Some(PACKETS[i].1);
+                            }
+                            i += 1;
+                        }
+                        None
+                    }
+                }
+            }
+        });
+        quote! {
+            pub mod #state {
+                #(#directions)*
+            }
+        }
+    });
+    quote! {
+        /// `==` for strings, which can't be called in const contexts.
+        const fn str_eq(a: &str, b: &str) -> bool {
+            let (a, b) = (a.as_bytes(), b.as_bytes());
+            if a.len() != b.len() {
+                return false;
+            }
+            let mut i = 0;
+            while i < a.len() {
+                if a[i] != b[i] {
+                    return false;
+                }
+                i += 1;
+            }
+            true
+        }
+
+        #(#states)*
+    }
+}
@@ -65,5 +65,5 @@ pub fn build() -> TokenStream {
             pub registry_entries: Vec<RegistryEntryData>,
         }
     };
-    TokenStream::from(expanded)
+    expanded
 }
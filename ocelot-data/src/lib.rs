@@ -1,2 +1,4 @@
+#[path = "generated/packets.rs"]
+pub mod packets;
 #[path = "generated/registry.rs"]
 pub mod registry;
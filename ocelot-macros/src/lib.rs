@@ -1,55 +1,125 @@
 use darling::{FromDeriveInput, FromField, FromVariant, ast};
 use proc_macro::TokenStream;
 use proc_macro_crate::FoundCrate;
-use quote::{format_ident, quote};
-use syn::{DeriveInput, Expr, Ident, Path, Type, parse_macro_input};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{DeriveInput, Expr, Ident, Path, Type, parse_macro_input, spanned::Spanned};
 
 const PRIMITIVES: [&str; 16] = [
     "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32",
     "f64", "char", "bool",
 ];
+const DATA_CRATE: &str = "ocelot-data";
 const PROTOCOL_CRATE: &str = "ocelot-protocol";
 const TYPES_CRATE: &str = "ocelot-types";
 
 #[derive(FromDeriveInput)]
-#[darling(attributes(codec), supports(enum_unit, struct_named))]
+#[darling(attributes(codec), supports(enum_any, struct_named))]
 struct CodecReceiver {
     ident: Ident,
     #[darling(rename = "via")]
     codec: Option<Path>,
+    /// Discriminant written before the fields of a variant, the same as `via` for unit enums.
+    tag: Option<Path>,
     data: ast::Data<CodecVariantReceiver, CodecFieldReceiver>,
 }
 
 #[derive(FromField)]
+#[darling(attributes(codec))]
 struct CodecFieldReceiver {
     ident: Option<Ident>,
+    ty: Type,
+    /// The `Option` field is only present if this holds, earlier fields are in scope as references.
+    #[darling(rename = "if")]
+    condition: Option<Expr>,
 }
 
 #[derive(FromVariant)]
+#[darling(attributes(codec))]
 struct CodecVariantReceiver {
     ident: Ident,
     discriminant: Option<Expr>,
+    /// Variants with fields can't declare a discriminant without a `repr`.
+    id: Option<Expr>,
+    /// Catch-all for unknown discriminants, a tuple variant holding the raw value.
+    #[darling(default)]
+    other: bool,
+    fields: ast::Fields<CodecFieldReceiver>,
 }
 
 #[derive(FromDeriveInput)]
 #[darling(attributes(packet), supports(struct_named))]
 struct PacketReceiver {
     ident: Ident,
-    id: i32,
-    data: ast::Data<(), PacketFieldReceiver>,
+    /// Resource name of the packet in the vanilla `packets.json` report, which its ID is taken from.
+    name: String,
+    /// Expected ID, the build fails if the report lists another one.
+    id: Option<i32>,
+    state: Path,
+    direction: Path,
+    data: ast::Data<(), CodecFieldReceiver>,
 }
 
-#[derive(FromField)]
-struct PacketFieldReceiver {
-    ident: Option<Ident>,
-    ty: Type,
+fn any_ident(tokens: proc_macro2::TokenStream, predicate: &impl Fn(&Ident) -> bool) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => predicate(&ident),
+        proc_macro2::TokenTree::Group(group) => any_ident(group.stream(), predicate),
+        _ => false,
+    })
+}
+
+fn mentions_ident(tokens: proc_macro2::TokenStream, name: &str) -> bool {
+    any_ident(tokens, &|ident| ident == name)
+}
+
+/// `RemainingBytes` and `SharedRemainingBytes` read until the end, so every field after them
+/// would be empty.
+fn check_remaining_bytes<'a>(types: impl ExactSizeIterator<Item = &'a Type>) -> Option<syn::Error> {
+    let last = types.len().saturating_sub(1);
+    types
+        .enumerate()
+        .find(|(i, ty)| {
+            *i != last
+                && any_ident(quote!(#ty), &|ident| {
+                    ident.to_string().ends_with("RemainingBytes")
+                })
+        })
+        .map(|(_, ty)| syn::Error::new_spanned(ty, "`RemainingBytes` has to be the last field!"))
+}
+
+/// Same as [`check_remaining_bytes`] at compile time, which also sees through type aliases.
+fn assert_remaining_bytes_last(
+    protocol_crate: &proc_macro2::TokenStream,
+    types: &[&Type],
+) -> proc_macro2::TokenStream {
+    let leading = types.split_last().map_or(&[][..], |(_, leading)| leading);
+    leading
+        .iter()
+        .map(|ty| {
+            quote_spanned! {ty.span()=>
+                const _: () = assert!(
+                    !<#ty as #protocol_crate::codec::MinecraftCodec>::READS_TO_END,
+                    "`RemainingBytes` has to be the last field!"
+                );
+            }
+        })
+        .collect()
+}
+
+/// Whether the last of the fields reads to the end, which makes the whole type do so.
+fn reads_to_end(
+    protocol_crate: &proc_macro2::TokenStream,
+    types: &[&Type],
+) -> proc_macro2::TokenStream {
+    match types.last() {
+        Some(ty) => quote! { <#ty as #protocol_crate::codec::MinecraftCodec>::READS_TO_END },
+        None => quote! { false },
+    }
 }
 
 fn get_root_path(crate_name: &str) -> proc_macro2::TokenStream {
-    match proc_macro_crate::crate_name(crate_name).expect(&format!(
-        "{} crate is not present in Cargo.toml!",
-        crate_name
-    )) {
+    match proc_macro_crate::crate_name(crate_name)
+        .unwrap_or_else(|_| panic!("{} crate is not present in Cargo.toml!", crate_name))
+    {
         FoundCrate::Itself => quote!(crate),
         FoundCrate::Name(name) => {
             let identifier = format_ident!("{}", name);
@@ -58,6 +128,133 @@ fn get_root_path(crate_name: &str) -> proc_macro2::TokenStream {
     }
 }
 
+/// Splits a variant name into words, e.g. `PlayerInfoUUID` becomes `Player Info UUID`.
+fn display_name(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut name = String::with_capacity(ident.len() + 4);
+    for (i, &char) in chars.iter().enumerate() {
+        if i > 0 && char.is_uppercase() {
+            let previous = chars[i - 1];
+            let acronym_end =
+                previous.is_uppercase() && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase() || previous.is_ascii_digit() || acronym_end {
+                name.push(' ');
+            }
+        }
+        name.push(char);
+    }
+    name
+}
+
+/// Local variables the fields are bound to, tuple fields are named after their index.
+fn field_bindings(fields: &[CodecFieldReceiver]) -> Vec<Ident> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            field
+                .ident
+                .clone()
+                .unwrap_or_else(|| format_ident!("field_{}", i))
+        })
+        .collect()
+}
+
+/// Pattern or constructor with every field bound, e.g. `Self::Variant { a, b }`.
+fn fields_pattern(
+    path: proc_macro2::TokenStream,
+    style: ast::Style,
+    bindings: &[Ident],
+) -> proc_macro2::TokenStream {
+    match style {
+        ast::Style::Struct => quote! { #path { #( #bindings ),* } },
+        ast::Style::Tuple => quote! { #path( #( #bindings ),* ) },
+        ast::Style::Unit => path,
+    }
+}
+
+/// Writes the fields, which have to be bound to references.
+fn encode_fields(
+    protocol_crate: &proc_macro2::TokenStream,
+    fields: &[CodecFieldReceiver],
+    bindings: &[Ident],
+) -> proc_macro2::TokenStream {
+    fields
+        .iter()
+        .zip(bindings)
+        .map(|(field, binding)| match &field.condition {
+            None => quote! { #protocol_crate::codec::MinecraftCodec::encode(#binding, writer)?; },
+            Some(condition) => {
+                let message = format!("`{}` doesn't match its condition!", binding);
+                quote! {
+                    match (#condition, #binding) {
+                        (true, Some(value)) => #protocol_crate::codec::MinecraftCodec::encode(value, writer)?,
+                        (false, None) => {}
+                        _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, #message)),
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+fn encoded_len_fields(
+    protocol_crate: &proc_macro2::TokenStream,
+    fields: &[CodecFieldReceiver],
+    bindings: &[Ident],
+) -> proc_macro2::TokenStream {
+    let lengths = fields.iter().zip(bindings).map(|(field, binding)| {
+        if field.condition.is_some() {
+            quote! { #binding.as_ref().map_or(0, #protocol_crate::codec::MinecraftCodec::encoded_len) }
+        } else {
+            quote! { #protocol_crate::codec::MinecraftCodec::encoded_len(#binding) }
+        }
+    });
+    quote! { 0 #( + #lengths )* }
+}
+
+/// Reads the fields into their bindings with `decode`, e.g. `decode_shared(buffer, budget)`.
+fn decode_fields(
+    protocol_crate: &proc_macro2::TokenStream,
+    fields: &[CodecFieldReceiver],
+    bindings: &[Ident],
+    decode: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    fields
+        .iter()
+        .zip(bindings)
+        .enumerate()
+        .map(|(i, (field, binding))| {
+            let ty = &field.ty;
+            let name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => i.to_string(),
+            };
+            let value = quote! {
+                #protocol_crate::codec::MinecraftCodec::#decode
+                    .map_err(|error| error.in_field(#name))?
+            };
+            match &field.condition {
+                None => quote! { let #binding: #ty = #value; },
+                Some(condition) => {
+                    let references = bindings[..i]
+                        .iter()
+                        .filter(|earlier| mentions_ident(quote!(#condition), &earlier.to_string()));
+                    quote! {
+                        let #binding: #ty = {
+                            let present: bool = {
+                                #( let #references = &#references; )*
+                                #condition
+                            };
+                            if present { Some(#value) } else { None }
+                        };
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
 #[proc_macro_derive(MinecraftCodec, attributes(codec))]
 pub fn codec_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -68,103 +265,350 @@ pub fn codec_derive(input: TokenStream) -> TokenStream {
     let protocol_crate = get_root_path(PROTOCOL_CRATE);
 
     let name = &receiver.ident;
+    let decode_with_budget = quote! { decode_with_budget(reader, budget) };
+    let decode_shared = quote! { decode_shared(buffer, budget) };
 
     let expanded = match receiver.data {
         ast::Data::Struct(fields) => {
-            let field_names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+            if let Some(error) = check_remaining_bytes(fields.iter().map(|field| &field.ty)) {
+                return error.to_compile_error().into();
+            }
+            let fields = fields.fields;
+            let types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+            let assertions = assert_remaining_bytes_last(&protocol_crate, &types);
+            let reads_to_end = reads_to_end(&protocol_crate, &types);
+            let bindings = field_bindings(&fields);
+            let pattern = fields_pattern(quote!(Self), ast::Style::Struct, &bindings);
+            let encode = encode_fields(&protocol_crate, &fields, &bindings);
+            let encoded_len = encoded_len_fields(&protocol_crate, &fields, &bindings);
+            let decode = decode_fields(&protocol_crate, &fields, &bindings, &decode_with_budget);
+            let decode_from_buffer =
+                decode_fields(&protocol_crate, &fields, &bindings, &decode_shared);
             quote! {
+                #assertions
                 impl #protocol_crate::codec::MinecraftCodec for #name {
+                    const READS_TO_END: bool = #reads_to_end;
+
                     fn encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-                        #( #protocol_crate::codec::MinecraftCodec::encode(&self.#field_names, writer)?; )*
+                        let #pattern = self;
+                        #encode
                         Ok(())
                     }
-                    fn decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
-                        Ok(Self {
-                            #( #field_names: #protocol_crate::codec::MinecraftCodec::decode(reader)?, )*
-                        })
+                    fn encoded_len(&self) -> usize {
+                        let #pattern = self;
+                        #encoded_len
+                    }
+                    fn decode_with_budget<R: std::io::Read>(
+                        reader: &mut R,
+                        budget: &mut #protocol_crate::codec::DecodeBudget,
+                    ) -> Result<Self, #protocol_crate::error::ProtocolError> {
+                        #decode
+                        Ok(#pattern)
+                    }
+                    fn decode_shared(
+                        buffer: &mut #protocol_crate::buffer::PacketBuffer,
+                        budget: &mut #protocol_crate::codec::DecodeBudget,
+                    ) -> Result<Self, #protocol_crate::error::ProtocolError> {
+                        #decode_from_buffer
+                        Ok(#pattern)
                     }
                 }
             }
         }
         ast::Data::Enum(variants) => {
-            let codec_path = &receiver.codec;
+            let Some(codec_path) = receiver.tag.as_ref().or(receiver.codec.as_ref()) else {
+                return syn::Error::new(name.span(), "Enums need a `tag` type!")
+                    .to_compile_error()
+                    .into();
+            };
             let codec_str = quote!(#codec_path).to_string();
-            let variant_names: Vec<_> = variants.iter().map(|variant| &variant.ident).collect();
-            let encode_patterns: Vec<_> = variants
+            let primitive = PRIMITIVES.contains(&codec_str.as_str());
+            let (others, variants): (Vec<_>, Vec<_>) =
+                variants.into_iter().partition(|variant| variant.other);
+            if let Some(other) = others.get(1) {
+                return syn::Error::new(other.ident.span(), "Only one variant can be `other`!")
+                    .to_compile_error()
+                    .into();
+            }
+            let other = others.first().map(|other| &other.ident);
+            if let Some(variant) = others.iter().find(|variant| {
+                variant.fields.style != ast::Style::Tuple || variant.fields.len() != 1
+            }) {
+                return syn::Error::new(
+                    variant.ident.span(),
+                    "`other` has to hold the discriminant, e.g. `Unknown(i32)`!",
+                )
+                .to_compile_error()
+                .into();
+            }
+            let mut discriminants = Vec::new();
+            for variant in &variants {
+                if let Some(error) =
+                    check_remaining_bytes(variant.fields.iter().map(|field| &field.ty))
+                {
+                    return error.to_compile_error().into();
+                }
+                match variant.discriminant.as_ref().or(variant.id.as_ref()) {
+                    Some(discriminant) => discriminants.push(discriminant),
+                    None => {
+                        return syn::Error::new(
+                            variant.ident.span(),
+                            "Explicit discriminant required!",
+                        )
+                        .to_compile_error()
+                        .into();
+                    }
+                }
+            }
+            let values: Vec<_> = discriminants
                 .iter()
-                .map(|variant| {
-                    let ident = &variant.ident;
-                    let discriminant = variant
-                        .discriminant
-                        .as_ref()
-                        .expect("Explicit discriminant required!");
-                    let value = if PRIMITIVES.contains(&codec_str.as_str()) {
+                .map(|discriminant| {
+                    if primitive {
                         quote! { (#discriminant as #codec_path) }
                     } else {
                         quote! { #codec_path(#discriminant) }
-                    };
-                    quote! {
-                        Self::#ident => <#codec_path as #protocol_crate::codec::MinecraftCodec>::encode(&#value, writer)?,
                     }
                 })
                 .collect();
-            let decode_patterns: Vec<_> = variants
+            let tag_patterns: Vec<_> = discriminants
                 .iter()
-                .map(|variant| {
-                    let ident = &variant.ident;
-                    let discriminant = &variant
-                        .discriminant
-                        .as_ref()
-                        .expect("Explicit discriminant required!");
-                    let pattern = if PRIMITIVES.contains(&codec_str.as_str()) {
+                .map(|discriminant| {
+                    if primitive {
                         quote! { #discriminant }
                     } else {
                         quote! { #codec_path(#discriminant) }
-                    };
-                    quote! { #pattern => Ok(Self::#ident), }
+                    }
                 })
                 .collect();
-            let display_names: Vec<String> = variants
+            let discriminant_value = if primitive {
+                quote! { id }
+            } else {
+                quote! { id.0 }
+            };
+            let variant_types: Vec<Vec<&Type>> = variants
                 .iter()
-                .map(|variant| {
-                    let ident = variant.ident.to_string();
-                    let mut lowercase = ident.to_lowercase();
-                    if let Some(first) = lowercase.get_mut(0..1) {
-                        first.make_ascii_uppercase();
-                    }
-                    lowercase
+                .map(|variant| variant.fields.iter().map(|field| &field.ty).collect())
+                .collect();
+            let assertions: proc_macro2::TokenStream = variant_types
+                .iter()
+                .map(|types| assert_remaining_bytes_last(&protocol_crate, types))
+                .collect();
+            let reads_to_end: Vec<_> = variant_types
+                .iter()
+                .map(|types| reads_to_end(&protocol_crate, types))
+                .collect();
+            let variant_names: Vec<_> = variants.iter().map(|variant| &variant.ident).collect();
+            let variant_bindings: Vec<_> = variants
+                .iter()
+                .map(|variant| field_bindings(&variant.fields.fields))
+                .collect();
+            let patterns: Vec<_> = variants
+                .iter()
+                .zip(&variant_bindings)
+                .map(|(variant, bindings)| {
+                    let ident = &variant.ident;
+                    fields_pattern(quote!(Self::#ident), variant.fields.style, bindings)
+                })
+                .collect();
+            let encode: Vec<_> = variants
+                .iter()
+                .zip(&variant_bindings)
+                .map(|(variant, bindings)| {
+                    encode_fields(&protocol_crate, &variant.fields.fields, bindings)
+                })
+                .collect();
+            let encoded_len: Vec<_> = variants
+                .iter()
+                .zip(&variant_bindings)
+                .map(|(variant, bindings)| {
+                    encoded_len_fields(&protocol_crate, &variant.fields.fields, bindings)
+                })
+                .collect();
+            let decode: Vec<_> = variants
+                .iter()
+                .zip(&variant_bindings)
+                .map(|(variant, bindings)| {
+                    decode_fields(
+                        &protocol_crate,
+                        &variant.fields.fields,
+                        bindings,
+                        &decode_with_budget,
+                    )
+                })
+                .collect();
+            let decode_from_buffer: Vec<_> = variants
+                .iter()
+                .zip(&variant_bindings)
+                .map(|(variant, bindings)| {
+                    decode_fields(
+                        &protocol_crate,
+                        &variant.fields.fields,
+                        bindings,
+                        &decode_shared,
+                    )
                 })
                 .collect();
+            let display_names: Vec<String> = variant_names
+                .iter()
+                .map(|ident| display_name(&ident.to_string()))
+                .collect();
+            let invalid_discriminant = match other {
+                Some(other) => quote! { Ok(Self::#other(#discriminant_value as _)) },
+                None => quote! {
+                    Err(#protocol_crate::error::ProtocolError::InvalidDiscriminant {
+                        name: stringify!(#name),
+                        value: #discriminant_value as i64,
+                    })
+                },
+            };
+            let other_value = if primitive {
+                quote! { (*value as #codec_path) }
+            } else {
+                quote! { #codec_path(*value as _) }
+            };
+            let other_encode = other.map(|other| {
+                quote! {
+                    Self::#other(value) => <#codec_path as #protocol_crate::codec::MinecraftCodec>::encode(&#other_value, writer)?,
+                }
+            });
+            let other_encoded_len = other.map(|other| {
+                quote! {
+                    Self::#other(value) => <#codec_path as #protocol_crate::codec::MinecraftCodec>::encoded_len(&#other_value),
+                }
+            });
+            let other_display = other.map(|other| {
+                let display_name = display_name(&other.to_string());
+                quote! {
+                    Self::#other(value) => write!(formatter, "{} ({})", #display_name, value),
+                }
+            });
+            let integer = match codec_str.as_str() {
+                "VarInt" => Some(quote!(i32)),
+                "VarLong" => Some(quote!(i64)),
+                _ if primitive => Some(quote!(#codec_path)),
+                _ => None,
+            };
+            let unit = variants
+                .iter()
+                .all(|variant| variant.fields.style == ast::Style::Unit);
+            let conversions = integer.filter(|_| unit).map(|integer| {
+                let from_integer = match other {
+                    Some(other) => quote! {
+                        impl From<#integer> for #name {
+                            fn from(value: #integer) -> Self {
+                                match value {
+                                    #( #discriminants => Self::#variant_names, )*
+                                    _ => Self::#other(value as _),
+                                }
+                            }
+                        }
+                    },
+                    None => quote! {
+                        impl TryFrom<#integer> for #name {
+                            type Error = #protocol_crate::error::ProtocolError;
+
+                            fn try_from(value: #integer) -> Result<Self, Self::Error> {
+                                match value {
+                                    #( #discriminants => Ok(Self::#variant_names), )*
+                                    _ => Err(#protocol_crate::error::ProtocolError::InvalidDiscriminant {
+                                        name: stringify!(#name),
+                                        value: value as i64,
+                                    }),
+                                }
+                            }
+                        }
+                    },
+                };
+                let other_integer = other.map(|other| quote! { #name::#other(value) => value as #integer, });
+                quote! {
+                    impl #name {
+                        /// Every known variant.
+                        pub const ALL: &'static [Self] = &[ #( Self::#variant_names, )* ];
+                    }
+                    #from_integer
+                    impl From<#name> for #integer {
+                        fn from(value: #name) -> Self {
+                            match value {
+                                #( #name::#variant_names => #discriminants as #integer, )*
+                                #other_integer
+                            }
+                        }
+                    }
+                }
+            });
             quote! {
+                #assertions
                 impl #protocol_crate::codec::MinecraftCodec for #name {
+                    const READS_TO_END: bool = false #( || #reads_to_end )*;
+
                     fn encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
                         match self {
-                            #(#encode_patterns)*
+                            #(
+                                #patterns => {
+                                    <#codec_path as #protocol_crate::codec::MinecraftCodec>::encode(&#values, writer)?;
+                                    #encode
+                                }
+                            )*
+                            #other_encode
                         }
                         Ok(())
                     }
-                    fn decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
-                        let id: #codec_path = <#codec_path as #protocol_crate::codec::MinecraftCodec>::decode(reader)?;
+                    fn encoded_len(&self) -> usize {
+                        match self {
+                            #(
+                                #patterns => <#codec_path as #protocol_crate::codec::MinecraftCodec>::encoded_len(&#values)
+                                    + #encoded_len,
+                            )*
+                            #other_encoded_len
+                        }
+                    }
+                    fn decode_with_budget<R: std::io::Read>(
+                        reader: &mut R,
+                        budget: &mut #protocol_crate::codec::DecodeBudget,
+                    ) -> Result<Self, #protocol_crate::error::ProtocolError> {
+                        let id: #codec_path = <#codec_path as #protocol_crate::codec::MinecraftCodec>::decode_with_budget(reader, budget)?;
                         match id {
-                            #(#decode_patterns)*
-                            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Unknown id for enum {}", stringify!(#name)))),
+                            #(
+                                #tag_patterns => {
+                                    #decode
+                                    Ok(#patterns)
+                                }
+                            )*
+                            _ => #invalid_discriminant,
+                        }
+                    }
+                    fn decode_shared(
+                        buffer: &mut #protocol_crate::buffer::PacketBuffer,
+                        budget: &mut #protocol_crate::codec::DecodeBudget,
+                    ) -> Result<Self, #protocol_crate::error::ProtocolError> {
+                        let id: #codec_path = <#codec_path as #protocol_crate::codec::MinecraftCodec>::decode_shared(buffer, budget)?;
+                        match id {
+                            #(
+                                #tag_patterns => {
+                                    #decode_from_buffer
+                                    Ok(#patterns)
+                                }
+                            )*
+                            _ => #invalid_discriminant,
                         }
                     }
                 }
                 impl std::fmt::Display for #name {
                     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                         match self {
-                            #( Self::#variant_names => write!(formatter, "{}", #display_names), )*
+                            #( Self::#variant_names { .. } => write!(formatter, "{}", #display_names), )*
+                            #other_display
                         }
                     }
                 }
+                #conversions
             }
         }
     };
     TokenStream::from(expanded)
 }
 
-#[proc_macro_derive(MinecraftPacket, attributes(packet))]
+#[proc_macro_derive(MinecraftPacket, attributes(packet, codec))]
 pub fn packet_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let receiver = match PacketReceiver::from_derive_input(&input) {
@@ -175,7 +619,42 @@ pub fn packet_derive(input: TokenStream) -> TokenStream {
     let types_crate = get_root_path(TYPES_CRATE);
 
     let name = &receiver.ident;
-    let packet_id = receiver.id;
+    let state = &receiver.state;
+    let direction = &receiver.direction;
+    let data_crate = get_root_path(DATA_CRATE);
+    let state_module = match quote!(#state).to_string().as_str() {
+        "Handshaking" => format_ident!("handshake"),
+        state => format_ident!("{}", state.to_lowercase()),
+    };
+    let direction_module = format_ident!("{}", quote!(#direction).to_string().to_lowercase());
+    let resource_name = &receiver.name;
+    let missing = format!(
+        "{} isn't a {} {} packet in packets.json!",
+        resource_name,
+        state_module,
+        quote!(#direction).to_string().to_lowercase()
+    );
+    let packet_id = quote! {
+        match #data_crate::packets::#state_module::#direction_module::id(#resource_name) {
+            Some(id) => id,
+            None => panic!(#missing),
+        }
+    };
+    // Evaluated even if nothing uses the ID, so a missing packet always fails the build.
+    let id_check = match receiver.id {
+        Some(id) => {
+            let message = format!(
+                "{} doesn't have the ID {:#04X} in packets.json!",
+                resource_name, id
+            );
+            quote! {
+                const _: () = assert!(<#name as #protocol_crate::packet::MinecraftPacket>::ID == #id, #message);
+            }
+        }
+        None => quote! {
+            const _: i32 = <#name as #protocol_crate::packet::MinecraftPacket>::ID;
+        },
+    };
     let fields = receiver.data.take_struct().unwrap().fields; // This can't fail at the moment.
     let field_names: Vec<&Ident> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
     let getter_names: Vec<Ident> = field_names
@@ -183,9 +662,36 @@ pub fn packet_derive(input: TokenStream) -> TokenStream {
         .map(|ident| format_ident!("get_{}", ident))
         .collect();
     let field_types: Vec<&syn::Type> = fields.iter().map(|f| &f.ty).collect();
+    let assertions = assert_remaining_bytes_last(&protocol_crate, &field_types);
+    let bindings = field_bindings(&fields);
+    let pattern = fields_pattern(quote!(Self), ast::Style::Struct, &bindings);
+    let encode = encode_fields(&protocol_crate, &fields, &bindings);
+    let encoded_len = encoded_len_fields(&protocol_crate, &fields, &bindings);
+    let decode = decode_fields(
+        &protocol_crate,
+        &fields,
+        &bindings,
+        &quote! { decode_shared(buffer, budget) },
+    );
+    if let Some(error) = check_remaining_bytes(field_types.iter().copied()) {
+        return error.to_compile_error().into();
+    }
+    let name_string = name.to_string();
+    let packet_name = format!(
+        "{}{}::{}",
+        quote!(#direction),
+        quote!(#state),
+        name_string.strip_suffix("Packet").unwrap_or(&name_string)
+    );
     let expanded = quote! {
+        #assertions
+        #id_check
+        const _: () = {
+            fn registered<P: #protocol_crate::packet::RegisteredPacket>() {}
+            let _ = registered::<#name>;
+        };
         impl #name {
-            pub const ID: i32 = #packet_id;
+            #[allow(clippy::too_many_arguments)]
             pub fn new(#( #field_names: #field_types, )*) -> Self {
                 Self {
                     #( #field_names, )*
@@ -198,19 +704,27 @@ pub fn packet_derive(input: TokenStream) -> TokenStream {
             )*
         }
         impl #protocol_crate::packet::MinecraftPacket for #name {
-            fn get_id(&self) -> i32 {
-                #packet_id
+            const ID: i32 = #packet_id;
+            const STATE: #protocol_crate::packet::ConnectionState = #protocol_crate::packet::ConnectionState::#state;
+            const DIRECTION: #protocol_crate::packet::PacketDirection = #protocol_crate::packet::PacketDirection::#direction;
+            const NAME: &'static str = #packet_name;
+
+            fn encoded_len(&self) -> usize {
+                let #pattern = self;
+                #encoded_len
             }
-            fn serialize(&self) -> std::io::Result<Vec<u8>> {
-                let mut writer = #protocol_crate::buffer::PacketWriter::new();
-                #protocol_crate::codec::MinecraftCodec::encode(&#types_crate::VarInt(#packet_id), &mut writer)?;
-                #( #protocol_crate::codec::MinecraftCodec::encode(&self.#field_names, &mut writer)?; )*
-                Ok(writer.build())
+            fn encode_with_id<W: std::io::Write>(&self, id: i32, writer: &mut W) -> std::io::Result<()> {
+                #protocol_crate::codec::MinecraftCodec::encode(&#types_crate::VarInt(id), writer)?;
+                let #pattern = self;
+                #encode
+                Ok(())
             }
-            fn deserialize(buffer: &mut #protocol_crate::buffer::PacketBuffer) -> std::io::Result<Self> {
-                Ok(Self {
-                    #( #field_names: <#field_types as #protocol_crate::codec::MinecraftCodec>::decode(buffer)?, )*
-                })
+            fn deserialize(buffer: &mut #protocol_crate::buffer::PacketBuffer) -> Result<Self, #protocol_crate::error::ProtocolError> {
+                let decode_fields = |buffer: &mut #protocol_crate::buffer::PacketBuffer, budget: &mut #protocol_crate::codec::DecodeBudget| -> Result<Self, #protocol_crate::error::ProtocolError> {
+                    #decode
+                    Ok(#pattern)
+                };
+                decode_fields(buffer, &mut #protocol_crate::codec::DecodeBudget::default()).map_err(|error| error.in_field(Self::NAME))
             }
         }
     };
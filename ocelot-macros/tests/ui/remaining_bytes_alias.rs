@@ -0,0 +1,12 @@
+use ocelot_macros::MinecraftCodec;
+use ocelot_protocol::codec::RemainingBytes;
+
+type Data = Option<RemainingBytes>;
+
+#[derive(MinecraftCodec)]
+struct Payload {
+    data: Data,
+    checksum: u8,
+}
+
+fn main() {}
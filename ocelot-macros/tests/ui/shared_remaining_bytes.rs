@@ -0,0 +1,10 @@
+use ocelot_macros::MinecraftCodec;
+use ocelot_protocol::codec::SharedRemainingBytes;
+
+#[derive(MinecraftCodec)]
+struct Payload {
+    data: SharedRemainingBytes,
+    checksum: u8,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use ocelot_macros::MinecraftPacket;
+use ocelot_protocol::packet::{RegisteredPacket, status::serverbound::ServerboundStatus};
+
+#[derive(MinecraftPacket)]
+#[packet(
+    name = "minecraft:status_request",
+    state = Status,
+    direction = Clientbound
+)]
+struct StatusRequestPacket {}
+
+impl RegisteredPacket for StatusRequestPacket {
+    type Enum = ServerboundStatus;
+}
+
+fn main() {}
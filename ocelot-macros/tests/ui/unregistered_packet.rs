@@ -0,0 +1,11 @@
+use ocelot_macros::MinecraftPacket;
+
+#[derive(MinecraftPacket)]
+#[packet(
+    name = "minecraft:status_request",
+    state = Status,
+    direction = Serverbound
+)]
+struct StatusRequestPacket {}
+
+fn main() {}
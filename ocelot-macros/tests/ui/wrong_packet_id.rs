@@ -0,0 +1,19 @@
+use ocelot_macros::MinecraftPacket;
+use ocelot_protocol::packet::{RegisteredPacket, status::serverbound::ServerboundStatus};
+
+#[derive(MinecraftPacket)]
+#[packet(
+    name = "minecraft:ping_request",
+    id = 0x00,
+    state = Status,
+    direction = Serverbound
+)]
+struct PingRequestPacket {
+    timestamp: i64,
+}
+
+impl RegisteredPacket for PingRequestPacket {
+    type Enum = ServerboundStatus;
+}
+
+fn main() {}
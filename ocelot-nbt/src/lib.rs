@@ -1,11 +1,34 @@
+pub mod text;
+
 use std::{
     collections::HashMap,
     io::{self, Read, Write},
 };
 
+use ocelot_types::budget::DecodeBudget;
+
 pub trait NbtBinaryCodec: Sized {
     fn encode_binary<W: Write>(&self, writer: &mut W) -> io::Result<()>;
-    fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Self>;
+    /// Decodes with a fresh [`DecodeBudget`] of the default size.
+    fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Self::decode_binary_with_budget(reader, &mut DecodeBudget::default())
+    }
+    /// Decodes and charges everything that is allocated for it to `budget`.
+    fn decode_binary_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> io::Result<Self>;
+}
+
+fn decode_length<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let length = i32::decode_binary(reader)?;
+    if length < 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Negative length ({})", length),
+        ));
+    }
+    Ok(length as usize)
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -56,7 +79,10 @@ impl NbtBinaryCodec for TagType {
         self.as_id().encode_binary(writer)
     }
 
-    fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn decode_binary_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> io::Result<Self> {
         match Self::from_id(u8::decode_binary(reader)?) {
             Some(res) => Ok(res),
             None => Err(io::Error::new(
@@ -67,7 +93,7 @@ impl NbtBinaryCodec for TagType {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Tag {
     // End does not exist in memory, so having it representable might increase bugs
     // End,
@@ -85,10 +111,14 @@ pub enum Tag {
     LongArray(Vec<i64>),
 }
 
-#[derive(Debug, PartialEq)]
-pub struct NamedTag(String, Tag);
+/// A tag with its name, the root of an NBT file. The root of network NBT has no name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedTag(pub String, pub Tag);
 
 impl Tag {
+    /// Vanilla rejects deeper nesting as well, it also keeps decoding from overflowing the stack.
+    pub const MAX_DEPTH: usize = 512;
+
     pub fn tag_type(&self) -> TagType {
         match self {
             Tag::Byte(_) => TagType::Byte,
@@ -112,7 +142,7 @@ impl Tag {
         writer.write_all(data)
     }
 
-    fn encode_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+    pub fn encode_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         match self {
             Self::Byte(data) => data.encode_binary(writer),
             Self::Short(data) => data.encode_binary(writer),
@@ -141,7 +171,48 @@ impl Tag {
             Self::LongArray(items) => items.encode_binary(writer),
         }
     }
-    fn decode_binary<R: Read>(tag_type: TagType, reader: &mut R) -> io::Result<Self> {
+    /// Number of bytes [`Tag::encode_binary`] writes.
+    pub fn binary_len(&self) -> usize {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) | Self::Float(_) => 4,
+            Self::Long(_) | Self::Double(_) => 8,
+            Self::ByteArray(items) => 4 + items.len(),
+            Self::String(string) => 2 + string.len(),
+            Self::List(_, nameless_tags) => {
+                1 + 4 + nameless_tags.iter().map(Self::binary_len).sum::<usize>()
+            }
+            Self::Compound(named_tags) => {
+                named_tags
+                    .iter()
+                    .map(|(name, tag)| 1 + 2 + name.len() + tag.binary_len())
+                    .sum::<usize>()
+                    + 1
+            }
+            Self::IntArray(items) => 4 + 4 * items.len(),
+            Self::LongArray(items) => 4 + 8 * items.len(),
+        }
+    }
+    pub fn decode_binary<R: Read>(
+        tag_type: TagType,
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> io::Result<Self> {
+        Self::decode_nested(tag_type, reader, budget, 0)
+    }
+    fn decode_nested<R: Read>(
+        tag_type: TagType,
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+        depth: usize,
+    ) -> io::Result<Self> {
+        if depth > Self::MAX_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("NBT nested deeper than {}", Self::MAX_DEPTH),
+            ));
+        }
         match tag_type {
             TagType::End => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -153,14 +224,18 @@ impl Tag {
             TagType::Long => Ok(Self::Long(NbtBinaryCodec::decode_binary(reader)?)),
             TagType::Float => Ok(Self::Float(NbtBinaryCodec::decode_binary(reader)?)),
             TagType::Double => Ok(Self::Double(NbtBinaryCodec::decode_binary(reader)?)),
-            TagType::ByteArray => Ok(Self::ByteArray(NbtBinaryCodec::decode_binary(reader)?)),
-            TagType::String => Ok(Self::String(NbtBinaryCodec::decode_binary(reader)?)),
+            TagType::ByteArray => Ok(Self::ByteArray(NbtBinaryCodec::decode_binary_with_budget(
+                reader, budget,
+            )?)),
+            TagType::String => Ok(Self::String(NbtBinaryCodec::decode_binary_with_budget(
+                reader, budget,
+            )?)),
             TagType::List => {
                 let tag_type = TagType::decode_binary(reader)?;
-                let len = i32::decode_binary(reader)? as usize;
-                let mut buffer = Vec::with_capacity(len);
+                let len = decode_length(reader)?;
+                let mut buffer = budget.vec_with_capacity(len)?;
                 for _ in 0..len {
-                    buffer.push(Self::decode_binary(tag_type, reader)?);
+                    buffer.push(Self::decode_nested(tag_type, reader, budget, depth + 1)?);
                 }
                 Ok(Self::List(tag_type, buffer))
             }
@@ -168,50 +243,68 @@ impl Tag {
                 let mut buffer = HashMap::new();
                 let mut tag_type = TagType::decode_binary(reader)?;
                 while tag_type != TagType::End {
-                    let name = String::decode_binary(reader)?;
-                    let tag = Tag::decode_binary(tag_type, reader)?;
+                    budget.allocate(size_of::<(String, Tag)>())?;
+                    let name = String::decode_binary_with_budget(reader, budget)?;
+                    let tag = Tag::decode_nested(tag_type, reader, budget, depth + 1)?;
                     buffer.insert(name, tag);
                     tag_type = TagType::decode_binary(reader)?;
                 }
                 Ok(Self::Compound(buffer))
             }
-            TagType::IntArray => Ok(Self::IntArray(NbtBinaryCodec::decode_binary(reader)?)),
-            TagType::LongArray => Ok(Self::LongArray(NbtBinaryCodec::decode_binary(reader)?)),
+            TagType::IntArray => Ok(Self::IntArray(NbtBinaryCodec::decode_binary_with_budget(
+                reader, budget,
+            )?)),
+            TagType::LongArray => Ok(Self::LongArray(NbtBinaryCodec::decode_binary_with_budget(
+                reader, budget,
+            )?)),
         }
     }
 }
 
 impl NamedTag {
-    fn encode_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+    pub fn encode_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         self.1.tag_type().encode_binary(writer)?;
         self.0.encode_binary(writer)?;
         self.1.encode_binary(writer)
     }
 
-    fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+    /// Returns `None` if the root is an end tag.
+    pub fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        let budget = &mut DecodeBudget::default();
         let tag_type = TagType::decode_binary(reader)?;
         if tag_type == TagType::End {
             Ok(None)
         } else {
             Ok(Some(Self(
-                String::decode_binary(reader)?,
-                Tag::decode_binary(tag_type, reader)?,
+                String::decode_binary_with_budget(reader, budget)?,
+                Tag::decode_binary(tag_type, reader, budget)?,
             )))
         }
     }
 
-    fn encode_binary_to_network<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+    /// Writes the root without its name, which has to be empty.
+    pub fn encode_binary_to_network<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         self.1.tag_type().encode_binary(writer)?;
         debug_assert_eq!(self.0, "");
         self.1.encode_binary(writer)
     }
 
-    fn decode_binary_from_network<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+    pub fn decode_binary_from_network<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        Self::decode_binary_from_network_with_budget(reader, &mut DecodeBudget::default())
+    }
+
+    pub fn decode_binary_from_network_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> io::Result<Option<Self>> {
         let tag_type = TagType::decode_binary(reader)?;
         if tag_type == TagType::End {
             Ok(None)
         } else {
-            Ok(Some(Self("".into(), Tag::decode_binary(tag_type, reader)?)))
+            Ok(Some(Self(
+                "".into(),
+                Tag::decode_binary(tag_type, reader, budget)?,
+            )))
         }
     }
 }
@@ -222,7 +315,10 @@ macro_rules! int_macro {
             fn encode_binary<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
                 writer.write_all(&self.to_be_bytes())
             }
-            fn decode_binary<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+            fn decode_binary_with_budget<R: std::io::Read>(
+                reader: &mut R,
+                _budget: &mut DecodeBudget,
+            ) -> std::io::Result<Self> {
                 let mut buffer = [0u8; size_of::<Self>()];
                 reader.read_exact(&mut buffer)?;
                 Ok(Self::from_be_bytes(buffer))
@@ -247,8 +343,12 @@ impl NbtBinaryCodec for String {
         writer.write_all(data)
     }
 
-    fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn decode_binary_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> io::Result<Self> {
         let len = u16::decode_binary(reader)? as usize;
+        budget.allocate(len)?;
         let mut buffer = Vec::new();
         reader.take(len as u64).read_to_end(&mut buffer)?;
         if buffer.len() != len {
@@ -267,17 +367,25 @@ impl<T: NbtBinaryCodec> NbtBinaryCodec for Vec<T> {
         self.iter().try_for_each(|data| data.encode_binary(writer))
     }
 
-    fn decode_binary<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let len = i32::decode_binary(reader)? as usize;
-        let mut buffer = Self::with_capacity(len);
+    fn decode_binary_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> io::Result<Self> {
+        let len = decode_length(reader)?;
+        let mut buffer = budget.vec_with_capacity(len)?;
         for _ in 0..len {
-            buffer.push(T::decode_binary(reader)?);
+            buffer.push(T::decode_binary_with_budget(reader, budget)?);
         }
         Ok(buffer)
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::excessive_precision,
+    clippy::unnecessary_mut_passed
+)]
 mod tests {
 
     use std::{fs, io::Cursor};
@@ -459,6 +567,32 @@ mod tests {
         assert_eq!(redecoded, decoded);
     }
 
+    #[test]
+    fn hostile_lengths() {
+        // A list claiming i32::MAX compounds in six bytes
+        let data = [9, 10, 0x7F, 0xFF, 0xFF, 0xFF];
+        let error =
+            Tag::decode_binary(TagType::List, &mut &data[1..], &mut DecodeBudget::default())
+                .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let data = [0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(Vec::<i64>::decode_binary(&mut &data[..]).is_err());
+
+        let data = [0x00, 0x04, b'a'];
+        let mut budget = DecodeBudget::new(3);
+        assert!(String::decode_binary_with_budget(&mut &data[..], &mut budget).is_err());
+
+        // A list of lists nested one level too deep, ending in an empty list
+        let mut data = vec![9];
+        data.extend([9, 0, 0, 0, 1].repeat(Tag::MAX_DEPTH + 1));
+        data.extend([0, 0, 0, 0, 0]);
+        let error = NamedTag::decode_binary_from_network(&mut &data[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        data.drain(1..6);
+        assert!(NamedTag::decode_binary_from_network(&mut &data[..]).is_ok());
+    }
+
     fn compare_nbt(nbt: &NamedTag, expected: &NamedTag, path: &mut Vec<String>) {
         assert_eq!(nbt.0, expected.0, "{:?}", path);
         path.push(nbt.0.clone());
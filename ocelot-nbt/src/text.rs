@@ -0,0 +1,977 @@
+//! Text components as vanilla writes them to NBT, e.g. in packets or registry entries.
+
+use std::{collections::HashMap, io};
+
+use ocelot_types::{
+    ResourceLocation,
+    text::{
+        ClickEvent, Content, Dialog, GenericComponent, HoverEvent, NbtSource, ObjectInfo,
+        PlayerProfile, ProfileProperty, ShadowColor, TextComponent,
+    },
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{Tag, TagType};
+
+impl From<&TextComponent> for Tag {
+    fn from(component: &TextComponent) -> Self {
+        if let Content::Text { text } = component.get_content()
+            && component.is_plain()
+        {
+            return Tag::String(text.clone());
+        }
+        let mut compound = HashMap::new();
+        let mut put = |key: &str, tag: Option<Tag>| {
+            if let Some(tag) = tag {
+                compound.insert(key.to_string(), tag);
+            }
+        };
+        match component.get_content() {
+            Content::Text { text } => put("text", Some(Tag::String(text.clone()))),
+            Content::Translatable {
+                translate,
+                fallback,
+                with,
+            } => {
+                put("translate", Some(Tag::String(translate.clone())));
+                put("fallback", fallback.clone().map(Tag::String));
+                put(
+                    "with",
+                    with.as_ref()
+                        .map(|with| list(with.iter().map(Tag::from).collect())),
+                );
+            }
+            Content::Score { score } => {
+                let score = HashMap::from([
+                    ("name".to_string(), Tag::String(score.name.clone())),
+                    (
+                        "objective".to_string(),
+                        Tag::String(score.objective.clone()),
+                    ),
+                ]);
+                put("score", Some(Tag::Compound(score)));
+            }
+            Content::Selector {
+                selector,
+                separator,
+            } => {
+                put("selector", Some(Tag::String(selector.clone())));
+                put("separator", separator.as_deref().map(Tag::from));
+            }
+            Content::Keybind { keybind } => put("keybind", Some(Tag::String(keybind.clone()))),
+            Content::Nbt {
+                nbt,
+                interpret,
+                separator,
+                source,
+            } => {
+                put("nbt", Some(Tag::String(nbt.clone())));
+                put("interpret", interpret.map(boolean));
+                put("separator", separator.as_deref().map(Tag::from));
+                match source {
+                    NbtSource::Block { block } => put("block", Some(Tag::String(block.clone()))),
+                    NbtSource::Entity { entity } => {
+                        put("entity", Some(Tag::String(entity.clone())))
+                    }
+                    NbtSource::Storage { storage } => {
+                        put("storage", Some(Tag::String(storage.to_string())))
+                    }
+                }
+            }
+            Content::Object {
+                object: ObjectInfo::Atlas { atlas, sprite },
+            } => {
+                put("object", Some(Tag::String("atlas".into())));
+                put(
+                    "atlas",
+                    atlas.as_ref().map(|atlas| Tag::String(atlas.to_string())),
+                );
+                put("sprite", Some(Tag::String(sprite.to_string())));
+            }
+            Content::Object {
+                object: ObjectInfo::Player { player, hat },
+            } => {
+                put("object", Some(Tag::String("player".into())));
+                put("player", Some(profile(player)));
+                put("hat", hat.map(boolean));
+            }
+        }
+        if !component.get_extra().is_empty() {
+            let extra = component.get_extra().iter().map(Tag::from).collect();
+            put("extra", Some(list(extra)));
+        }
+        put(
+            "color",
+            component.get_color().map(|color| Tag::String(color.into())),
+        );
+        put(
+            "font",
+            component
+                .get_font()
+                .map(|font| Tag::String(font.to_string())),
+        );
+        put("bold", component.get_bold().map(boolean));
+        put("italic", component.get_italic().map(boolean));
+        put("underlined", component.get_underlined().map(boolean));
+        put("strikethrough", component.get_strikethrough().map(boolean));
+        put("obfuscated", component.get_obfuscated().map(boolean));
+        put(
+            "shadow_color",
+            component.get_shadow_color().map(|color| match color {
+                ShadowColor::Int(argb) => Tag::Int(*argb),
+                ShadowColor::FloatArray(rgba) => Tag::List(
+                    TagType::Float,
+                    rgba.iter().copied().map(Tag::Float).collect(),
+                ),
+            }),
+        );
+        put(
+            "insertion",
+            component
+                .get_insertion()
+                .map(|insertion| Tag::String(insertion.into())),
+        );
+        put("click_event", component.get_click_event().map(click_event));
+        put("hover_event", component.get_hover_event().map(hover_event));
+        Tag::Compound(compound)
+    }
+}
+
+impl TryFrom<&Tag> for TextComponent {
+    type Error = io::Error;
+
+    fn try_from(tag: &Tag) -> io::Result<Self> {
+        match tag {
+            Tag::String(text) => Ok(TextComponent::text(text.clone())),
+            // The first component of a list is the parent of the others.
+            Tag::List(_, items) => {
+                let mut items = items.iter().map(unwrap_element);
+                let first = items
+                    .next()
+                    .ok_or_else(|| invalid("Empty text component list".into()))?;
+                items.try_fold(TextComponent::try_from(first)?, |parent, item| {
+                    Ok(parent.append(TextComponent::try_from(item)?))
+                })
+            }
+            Tag::Compound(compound) => decode_compound(compound),
+            tag => Err(invalid(format!(
+                "Text component can't be a {:?} tag",
+                tag.tag_type()
+            ))),
+        }
+    }
+}
+
+fn decode_compound(compound: &HashMap<String, Tag>) -> io::Result<TextComponent> {
+    let content_type = match compound.get("type") {
+        Some(tag) => string(tag, "type")?,
+        None if compound.contains_key("text") => "text",
+        None if compound.contains_key("translate") => "translatable",
+        None if compound.contains_key("score") => "score",
+        None if compound.contains_key("selector") => "selector",
+        None if compound.contains_key("keybind") => "keybind",
+        None if compound.contains_key("nbt") => "nbt",
+        None if compound.contains_key("object") => "object",
+        None => return Err(invalid("Text component without content".into())),
+    };
+    let mut component = match content_type {
+        "text" => TextComponent::text(string(field(compound, "text")?, "text")?),
+        "translatable" => {
+            let mut builder =
+                TextComponent::translate(string(field(compound, "translate")?, "translate")?);
+            if let Some(fallback) = compound.get("fallback") {
+                builder = builder.with_fallback(string(fallback, "fallback")?);
+            }
+            if let Some(with) = compound.get("with") {
+                let args = items(with, "with")?
+                    .map(argument)
+                    .collect::<io::Result<_>>()?;
+                builder = builder.with_args(args);
+            }
+            builder.build()
+        }
+        "score" => {
+            let score = self::compound(field(compound, "score")?, "score")?;
+            TextComponent::score(
+                string(field(score, "name")?, "name")?,
+                string(field(score, "objective")?, "objective")?,
+            )
+        }
+        "selector" => TextComponent::new(Content::Selector {
+            selector: string(field(compound, "selector")?, "selector")?.to_string(),
+            separator: optional(compound, "separator", separator)?,
+        }),
+        "keybind" => TextComponent::keybind(string(field(compound, "keybind")?, "keybind")?),
+        "nbt" => {
+            let source = if let Some(block) = compound.get("block") {
+                NbtSource::Block {
+                    block: string(block, "block")?.to_string(),
+                }
+            } else if let Some(entity) = compound.get("entity") {
+                NbtSource::Entity {
+                    entity: string(entity, "entity")?.to_string(),
+                }
+            } else if let Some(storage) = compound.get("storage") {
+                NbtSource::Storage {
+                    storage: resource_location(storage, "storage")?,
+                }
+            } else {
+                return Err(invalid("NBT component without a source".into()));
+            };
+            TextComponent::new(Content::Nbt {
+                nbt: string(field(compound, "nbt")?, "nbt")?.to_string(),
+                interpret: optional(compound, "interpret", decode_boolean)?,
+                separator: optional(compound, "separator", separator)?,
+                source,
+            })
+        }
+        "object" => {
+            let object = match string(field(compound, "object")?, "object")? {
+                "atlas" => ObjectInfo::Atlas {
+                    atlas: optional(compound, "atlas", resource_location)?,
+                    sprite: resource_location(field(compound, "sprite")?, "sprite")?,
+                },
+                "player" => ObjectInfo::Player {
+                    player: decode_profile(field(compound, "player")?)?,
+                    hat: optional(compound, "hat", decode_boolean)?,
+                },
+                object => return Err(invalid(format!("Unknown object type {}", object))),
+            };
+            TextComponent::new(Content::Object { object })
+        }
+        content_type => {
+            return Err(invalid(format!(
+                "Unknown text component type {}",
+                content_type
+            )));
+        }
+    };
+    if let Some(extra) = compound.get("extra") {
+        for child in items(extra, "extra")? {
+            component = component.append(TextComponent::try_from(child)?);
+        }
+    }
+    if let Some(color) = compound.get("color") {
+        component = component.color(string(color, "color")?);
+    }
+    if let Some(font) = compound.get("font") {
+        component = component.font(resource_location(font, "font")?);
+    }
+    if let Some(bold) = compound.get("bold") {
+        component = component.bold(decode_boolean(bold, "bold")?);
+    }
+    if let Some(italic) = compound.get("italic") {
+        component = component.italic(decode_boolean(italic, "italic")?);
+    }
+    if let Some(underlined) = compound.get("underlined") {
+        component = component.underlined(decode_boolean(underlined, "underlined")?);
+    }
+    if let Some(strikethrough) = compound.get("strikethrough") {
+        component = component.strikethrough(decode_boolean(strikethrough, "strikethrough")?);
+    }
+    if let Some(obfuscated) = compound.get("obfuscated") {
+        component = component.obfuscated(decode_boolean(obfuscated, "obfuscated")?);
+    }
+    if let Some(shadow_color) = compound.get("shadow_color") {
+        component = component.shadow_color(decode_shadow_color(shadow_color)?);
+    }
+    if let Some(insertion) = compound.get("insertion") {
+        component = component.insertion(string(insertion, "insertion")?);
+    }
+    if let Some(click_event) = compound.get("click_event") {
+        component = component.click_event(decode_click_event(click_event)?);
+    }
+    if let Some(hover_event) = compound.get("hover_event") {
+        component = component.hover_event(decode_hover_event(hover_event)?);
+    }
+    Ok(component)
+}
+
+/// Translation arguments may also be numbers or booleans. [`TextComponent`] can only hold them
+/// as their text, so they are written back as strings, and a boolean (a byte in NBT) as `1`/`0`.
+fn argument(tag: &Tag) -> io::Result<TextComponent> {
+    let text = match tag {
+        Tag::Byte(value) => value.to_string(),
+        Tag::Short(value) => value.to_string(),
+        Tag::Int(value) => value.to_string(),
+        Tag::Long(value) => value.to_string(),
+        Tag::Float(value) => value.to_string(),
+        Tag::Double(value) => value.to_string(),
+        tag => return TextComponent::try_from(tag),
+    };
+    Ok(TextComponent::text(text))
+}
+
+fn separator(tag: &Tag, _name: &str) -> io::Result<Box<TextComponent>> {
+    TextComponent::try_from(tag).map(Box::new)
+}
+
+fn profile(profile: &PlayerProfile) -> Tag {
+    let mut compound = HashMap::new();
+    if let Some(name) = &profile.name {
+        compound.insert("name".to_string(), Tag::String(name.clone()));
+    }
+    if let Some(id) = profile.id {
+        compound.insert("id".to_string(), uuid(id));
+    }
+    if !profile.properties.is_empty() {
+        let properties = profile
+            .properties
+            .iter()
+            .map(|property| {
+                let mut compound = HashMap::from([
+                    ("name".to_string(), Tag::String(property.name.clone())),
+                    ("value".to_string(), Tag::String(property.value.clone())),
+                ]);
+                if let Some(signature) = &property.signature {
+                    compound.insert("signature".to_string(), Tag::String(signature.clone()));
+                }
+                Tag::Compound(compound)
+            })
+            .collect();
+        compound.insert("properties".to_string(), list(properties));
+    }
+    Tag::Compound(compound)
+}
+
+/// Vanilla also accepts just the name of the player.
+fn decode_profile(tag: &Tag) -> io::Result<PlayerProfile> {
+    let compound = match tag {
+        Tag::String(name) => {
+            return Ok(PlayerProfile {
+                name: Some(name.clone()),
+                ..Default::default()
+            });
+        }
+        tag => compound(tag, "player")?,
+    };
+    let owned_string = |tag: &Tag, name: &str| string(tag, name).map(String::from);
+    let properties = match compound.get("properties") {
+        Some(properties) => items(properties, "properties")?
+            .map(|property| {
+                let property = self::compound(property, "properties")?;
+                Ok(ProfileProperty {
+                    name: owned_string(field(property, "name")?, "name")?,
+                    value: owned_string(field(property, "value")?, "value")?,
+                    signature: optional(property, "signature", owned_string)?,
+                })
+            })
+            .collect::<io::Result<_>>()?,
+        None => Vec::new(),
+    };
+    Ok(PlayerProfile {
+        name: optional(compound, "name", owned_string)?,
+        id: optional(compound, "id", decode_uuid)?,
+        properties,
+    })
+}
+
+fn decode_shadow_color(tag: &Tag) -> io::Result<ShadowColor> {
+    match tag {
+        Tag::Int(argb) => Ok(ShadowColor::Int(*argb)),
+        Tag::List(_, items) if items.len() == 4 => {
+            let mut rgba = [0.0; 4];
+            for (channel, item) in rgba.iter_mut().zip(items) {
+                *channel = match item {
+                    Tag::Float(value) => *value,
+                    Tag::Double(value) => *value as f32,
+                    _ => return Err(invalid("`shadow_color` must hold floats".into())),
+                };
+            }
+            Ok(ShadowColor::FloatArray(rgba))
+        }
+        _ => Err(invalid(
+            "`shadow_color` must be an int or a list of 4 floats".into(),
+        )),
+    }
+}
+
+fn click_event(event: &ClickEvent) -> Tag {
+    let (action, fields) = match event {
+        ClickEvent::OpenUrl { url } => ("open_url", vec![("url", Tag::String(url.clone()))]),
+        ClickEvent::OpenFile { path } => ("open_file", vec![("path", Tag::String(path.clone()))]),
+        ClickEvent::RunCommand { command } => (
+            "run_command",
+            vec![("command", Tag::String(command.clone()))],
+        ),
+        ClickEvent::SuggestCommand { command } => (
+            "suggest_command",
+            vec![("command", Tag::String(command.clone()))],
+        ),
+        ClickEvent::ChangePage { page } => ("change_page", vec![("page", Tag::Int(*page))]),
+        ClickEvent::CopyToClipboard { value } => (
+            "copy_to_clipboard",
+            vec![("value", Tag::String(value.clone()))],
+        ),
+        ClickEvent::Custom { id, payload } => {
+            let mut fields = vec![("id", Tag::String(id.to_string()))];
+            fields.extend(
+                payload
+                    .as_ref()
+                    .and_then(json_tag)
+                    .map(|tag| ("payload", tag)),
+            );
+            ("custom", fields)
+        }
+        ClickEvent::ShowDialog { dialog } => {
+            let dialog = match dialog {
+                Dialog::Id(id) => Some(Tag::String(id.to_string())),
+                Dialog::Inline(dialog) => json_tag(dialog),
+            };
+            (
+                "show_dialog",
+                dialog.map(|tag| ("dialog", tag)).into_iter().collect(),
+            )
+        }
+    };
+    event_tag(action, fields)
+}
+
+fn decode_click_event(tag: &Tag) -> io::Result<ClickEvent> {
+    let compound = compound(tag, "click_event")?;
+    let string_field = |key| Ok::<_, io::Error>(string(field(compound, key)?, key)?.to_string());
+    Ok(match string(field(compound, "action")?, "action")? {
+        "open_url" => ClickEvent::OpenUrl {
+            url: string_field("url")?,
+        },
+        "open_file" => ClickEvent::OpenFile {
+            path: string_field("path")?,
+        },
+        "run_command" => ClickEvent::RunCommand {
+            command: string_field("command")?,
+        },
+        "suggest_command" => ClickEvent::SuggestCommand {
+            command: string_field("command")?,
+        },
+        "change_page" => ClickEvent::ChangePage {
+            page: int(field(compound, "page")?, "page")?,
+        },
+        "copy_to_clipboard" => ClickEvent::CopyToClipboard {
+            value: string_field("value")?,
+        },
+        "show_dialog" => ClickEvent::ShowDialog {
+            dialog: match field(compound, "dialog")? {
+                dialog @ Tag::String(_) => Dialog::Id(resource_location(dialog, "dialog")?),
+                dialog => Dialog::Inline(tag_json(dialog)),
+            },
+        },
+        "custom" => ClickEvent::Custom {
+            id: resource_location(field(compound, "id")?, "id")?,
+            payload: compound.get("payload").map(tag_json),
+        },
+        action => {
+            return Err(invalid(format!("Unknown click event action {}", action)));
+        }
+    })
+}
+
+fn hover_event(event: &HoverEvent) -> Tag {
+    match event {
+        HoverEvent::ShowText { value } => {
+            event_tag("show_text", vec![("value", Tag::from(value.as_ref()))])
+        }
+        HoverEvent::ShowItem {
+            id,
+            count,
+            components,
+        } => {
+            let mut fields = vec![
+                ("id", Tag::String(id.to_string())),
+                ("count", Tag::Int(*count)),
+            ];
+            fields.extend(
+                components
+                    .as_ref()
+                    .and_then(json_tag)
+                    .map(|tag| ("components", tag)),
+            );
+            event_tag("show_item", fields)
+        }
+        HoverEvent::ShowEntity { id, uuid, name } => {
+            let mut fields = vec![
+                ("id", Tag::String(id.to_string())),
+                ("uuid", self::uuid(*uuid)),
+            ];
+            fields.extend(name.as_deref().map(|name| ("name", Tag::from(name))));
+            event_tag("show_entity", fields)
+        }
+    }
+}
+
+fn decode_hover_event(tag: &Tag) -> io::Result<HoverEvent> {
+    let compound = compound(tag, "hover_event")?;
+    match string(field(compound, "action")?, "action")? {
+        "show_text" => Ok(HoverEvent::ShowText {
+            value: Box::new(TextComponent::try_from(field(compound, "value")?)?),
+        }),
+        "show_item" => Ok(HoverEvent::ShowItem {
+            id: resource_location(field(compound, "id")?, "id")?,
+            count: optional(compound, "count", int)?.unwrap_or(1),
+            components: compound.get("components").map(tag_json),
+        }),
+        "show_entity" => Ok(HoverEvent::ShowEntity {
+            id: resource_location(field(compound, "id")?, "id")?,
+            uuid: decode_uuid(field(compound, "uuid")?, "uuid")?,
+            name: optional(compound, "name", separator)?,
+        }),
+        action => Err(invalid(format!("Unknown hover event action {}", action))),
+    }
+}
+
+fn event_tag(action: &str, fields: Vec<(&str, Tag)>) -> Tag {
+    let mut compound = HashMap::from([("action".to_string(), Tag::String(action.into()))]);
+    compound.extend(fields.into_iter().map(|(key, tag)| (key.to_string(), tag)));
+    Tag::Compound(compound)
+}
+
+/// Builds a list like vanilla, which wraps the elements of mixed lists into compounds with an empty key.
+fn list(items: Vec<Tag>) -> Tag {
+    let tag_type = match items.first() {
+        None => return Tag::List(TagType::End, items),
+        Some(first) if items.iter().all(|item| item.tag_type() == first.tag_type()) => {
+            first.tag_type()
+        }
+        Some(_) => TagType::Compound,
+    };
+    if tag_type != TagType::Compound {
+        return Tag::List(tag_type, items);
+    }
+    let items = items
+        .into_iter()
+        .map(|item| match item {
+            Tag::Compound(compound) if !is_wrapper(&compound) => Tag::Compound(compound),
+            item => Tag::Compound(HashMap::from([(String::new(), item)])),
+        })
+        .collect();
+    Tag::List(TagType::Compound, items)
+}
+
+fn is_wrapper(compound: &HashMap<String, Tag>) -> bool {
+    compound.len() == 1 && compound.contains_key("")
+}
+
+fn unwrap_element(tag: &Tag) -> &Tag {
+    match tag {
+        Tag::Compound(compound) if is_wrapper(compound) => &compound[""],
+        tag => tag,
+    }
+}
+
+fn items<'a>(tag: &'a Tag, name: &str) -> io::Result<impl Iterator<Item = &'a Tag>> {
+    match tag {
+        Tag::List(_, items) => Ok(items.iter().map(unwrap_element)),
+        _ => Err(invalid(format!("`{}` must be a list", name))),
+    }
+}
+
+/// Custom payloads are JSON in memory, so their numbers lose the exact NBT type and nulls are dropped.
+fn json_tag(value: &Value) -> Option<Tag> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(value) => boolean(*value),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => i32::try_from(value).map_or(Tag::Long(value), Tag::Int),
+            None => Tag::Double(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(string) => Tag::String(string.clone()),
+        Value::Array(items) => list(items.iter().filter_map(json_tag).collect()),
+        Value::Object(object) => Tag::Compound(
+            object
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), json_tag(value)?)))
+                .collect(),
+        ),
+    })
+}
+
+fn tag_json(tag: &Tag) -> Value {
+    match tag {
+        Tag::Byte(value) => (*value).into(),
+        Tag::Short(value) => (*value).into(),
+        Tag::Int(value) => (*value).into(),
+        Tag::Long(value) => (*value).into(),
+        Tag::Float(value) => (*value).into(),
+        Tag::Double(value) => (*value).into(),
+        Tag::ByteArray(items) => items.iter().copied().collect(),
+        Tag::String(string) => string.clone().into(),
+        Tag::List(_, items) => items.iter().map(unwrap_element).map(tag_json).collect(),
+        Tag::Compound(compound) => Value::Object(
+            compound
+                .iter()
+                .map(|(key, tag)| (key.clone(), tag_json(tag)))
+                .collect(),
+        ),
+        Tag::IntArray(items) => items.iter().copied().collect(),
+        Tag::LongArray(items) => items.iter().copied().collect(),
+    }
+}
+
+fn boolean(value: bool) -> Tag {
+    Tag::Byte(value as i8)
+}
+
+fn decode_boolean(tag: &Tag, name: &str) -> io::Result<bool> {
+    match tag {
+        Tag::Byte(value) => Ok(*value != 0),
+        Tag::Short(value) => Ok(*value != 0),
+        Tag::Int(value) => Ok(*value != 0),
+        Tag::Long(value) => Ok(*value != 0),
+        _ => Err(invalid(format!("`{}` must be a boolean", name))),
+    }
+}
+
+fn uuid(uuid: Uuid) -> Tag {
+    let bits = uuid.as_u128();
+    Tag::IntArray([96, 64, 32, 0].map(|shift| (bits >> shift) as i32).to_vec())
+}
+
+/// UUIDs are written as four ints, but strings are accepted as well.
+fn decode_uuid(tag: &Tag, name: &str) -> io::Result<Uuid> {
+    match tag {
+        Tag::IntArray(ints) if ints.len() == 4 => Ok(Uuid::from_u128(
+            ints.iter()
+                .fold(0, |bits, int| (bits << 32) | *int as u32 as u128),
+        )),
+        Tag::String(string) => {
+            Uuid::parse_str(string).map_err(|error| invalid(format!("`{}`: {}", name, error)))
+        }
+        _ => Err(invalid(format!("`{}` must be a UUID", name))),
+    }
+}
+
+fn int(tag: &Tag, name: &str) -> io::Result<i32> {
+    match tag {
+        Tag::Int(value) => Ok(*value),
+        _ => Err(invalid(format!("`{}` must be an int", name))),
+    }
+}
+
+fn optional<T>(
+    compound: &HashMap<String, Tag>,
+    name: &str,
+    decode: impl Fn(&Tag, &str) -> io::Result<T>,
+) -> io::Result<Option<T>> {
+    compound.get(name).map(|tag| decode(tag, name)).transpose()
+}
+
+fn field<'a>(compound: &'a HashMap<String, Tag>, name: &str) -> io::Result<&'a Tag> {
+    compound
+        .get(name)
+        .ok_or_else(|| invalid(format!("Missing `{}`", name)))
+}
+
+fn compound<'a>(tag: &'a Tag, name: &str) -> io::Result<&'a HashMap<String, Tag>> {
+    match tag {
+        Tag::Compound(compound) => Ok(compound),
+        _ => Err(invalid(format!("`{}` must be a compound", name))),
+    }
+}
+
+fn string<'a>(tag: &'a Tag, name: &str) -> io::Result<&'a str> {
+    match tag {
+        Tag::String(string) => Ok(string),
+        _ => Err(invalid(format!("`{}` must be a string", name))),
+    }
+}
+
+fn resource_location(tag: &Tag, name: &str) -> io::Result<ResourceLocation> {
+    ResourceLocation::try_from(string(tag, name)?.to_string())
+        .map_err(|error| invalid(format!("`{}`: {}", name, error)))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NamedTag;
+
+    fn decode(data: &[u8]) -> Tag {
+        NamedTag::decode_binary_from_network(&mut &data[..])
+            .unwrap()
+            .unwrap()
+            .1
+    }
+
+    fn round_trip(tag: &Tag) -> TextComponent {
+        let component = TextComponent::try_from(tag).unwrap();
+        assert_eq!(&Tag::from(&component), tag);
+        component
+    }
+
+    /// Compares the re-encoded bytes, which only have a fixed order for strings and compounds with
+    /// a single key, as compound entries are written in map order.
+    fn round_trip_bytes(data: &[u8]) -> TextComponent {
+        let component = round_trip(&decode(data));
+        let mut encoded = Vec::new();
+        NamedTag(String::new(), Tag::from(&component))
+            .encode_binary_to_network(&mut encoded)
+            .unwrap();
+        assert_eq!(encoded, data);
+        component
+    }
+
+    #[test]
+    fn plain_text() {
+        // Hand-assembled in the layout vanilla uses for Component.literal("Bye!")
+        let component = round_trip_bytes(&[8, 0, 4, b'B', b'y', b'e', b'!']);
+        assert_eq!(component, TextComponent::text("Bye!"));
+
+        // A plain component with a style can't be shortened.
+        let component = TextComponent::text("Bye!").bold(false);
+        assert!(matches!(Tag::from(&component), Tag::Compound(_)));
+        let component = TextComponent::text("Bye!").append(TextComponent::text("?"));
+        assert!(matches!(Tag::from(&component), Tag::Compound(_)));
+    }
+
+    #[test]
+    fn translatable() {
+        // Hand-assembled in the layout vanilla uses for
+        // Component.translatable("multiplayer.disconnect.server_shutdown")
+        let mut data = vec![10, 8, 0, 9];
+        data.extend(b"translate");
+        data.extend([0, 38]);
+        data.extend(b"multiplayer.disconnect.server_shutdown");
+        data.push(0);
+        let component = round_trip_bytes(&data);
+        assert_eq!(
+            component,
+            TextComponent::translate("multiplayer.disconnect.server_shutdown").build()
+        );
+
+        // Arguments may be primitives, which come back as strings
+        let tag = Tag::Compound(HashMap::from([
+            ("translate".into(), Tag::String("chat.type.text".into())),
+            (
+                "with".into(),
+                Tag::List(TagType::Int, vec![Tag::Int(4), Tag::Int(2)]),
+            ),
+        ]));
+        let component = TextComponent::try_from(&tag).unwrap();
+        let args = vec![TextComponent::text("4"), TextComponent::text("2")];
+        assert_eq!(
+            component,
+            TextComponent::translate("chat.type.text")
+                .with_args(args)
+                .build()
+        );
+        let Tag::Compound(compound) = Tag::from(&component) else {
+            panic!("Expected a compound!");
+        };
+        assert_eq!(
+            compound["with"],
+            Tag::List(
+                TagType::String,
+                vec![Tag::String("4".into()), Tag::String("2".into())]
+            )
+        );
+    }
+
+    #[test]
+    fn extra_lists() {
+        // Hand-assembled in the layout vanilla uses for
+        // Component.literal("a").append("b").append(Component.literal("c").withStyle(BOLD)),
+        // whose mixed extra list wraps the string into {"": "b"}. The root has two keys, so only
+        // the tags are compared.
+        let mut data = vec![10, 8, 0, 4];
+        data.extend(b"text");
+        data.extend([0, 1, b'a', 9, 0, 5]);
+        data.extend(b"extra");
+        data.extend([10, 0, 0, 0, 2]);
+        data.extend([8, 0, 0, 0, 1, b'b', 0]);
+        data.extend([8, 0, 4]);
+        data.extend(b"text");
+        data.extend([0, 1, b'c', 1, 0, 4]);
+        data.extend(b"bold");
+        data.extend([1, 0, 0]);
+        let component = round_trip(&decode(&data));
+        let expected = TextComponent::text("a")
+            .append(TextComponent::text("b"))
+            .append(TextComponent::text("c").bold(true));
+        assert_eq!(component, expected);
+
+        // Only plain children make a list of strings
+        let component = TextComponent::text("a").append(TextComponent::text("b"));
+        let Tag::Compound(compound) = Tag::from(&component) else {
+            panic!("Expected a compound!");
+        };
+        assert_eq!(
+            compound["extra"],
+            Tag::List(TagType::String, vec![Tag::String("b".into())])
+        );
+
+        // A list at the root is the first component with the others appended
+        let tag = Tag::List(
+            TagType::String,
+            vec![Tag::String("a".into()), Tag::String("b".into())],
+        );
+        assert_eq!(TextComponent::try_from(&tag).unwrap(), component);
+        assert!(TextComponent::try_from(&Tag::List(TagType::End, Vec::new())).is_err());
+    }
+
+    #[test]
+    fn styles_and_events() {
+        let component = TextComponent::keybind("key.jump")
+            .color("#FF8800")
+            .font(ResourceLocation::from_vanilla("uniform").unwrap())
+            .italic(true)
+            .underlined(false)
+            .strikethrough(true)
+            .obfuscated(false)
+            .shadow_color(ShadowColor::Int(-16777216))
+            .insertion("jump")
+            .click_event(ClickEvent::Custom {
+                id: ResourceLocation::from("ocelot", "jump").unwrap(),
+                payload: Some(serde_json::json!({"height": 2, "tags": ["a", "b"]})),
+            })
+            .hover_event(HoverEvent::ShowText {
+                value: Box::new(TextComponent::text("Jump!").color("green")),
+            });
+        let tag = Tag::from(&component);
+        assert_eq!(round_trip(&tag), component);
+
+        let Tag::Compound(compound) = &tag else {
+            panic!("Expected a compound!");
+        };
+        assert_eq!(compound["italic"], Tag::Byte(1));
+        assert_eq!(compound["shadow_color"], Tag::Int(-16777216));
+
+        // The float form of shadow_color
+        let component = TextComponent::text("shadow")
+            .shadow_color(ShadowColor::FloatArray([1.0, 0.5, 0.25, 1.0]))
+            .click_event(ClickEvent::ChangePage { page: 3 });
+        assert_eq!(round_trip(&Tag::from(&component)), component);
+
+        let tag = Tag::Compound(HashMap::from([
+            ("text".into(), Tag::String("hi".into())),
+            ("bold".into(), Tag::String("yes".into())),
+        ]));
+        assert!(TextComponent::try_from(&tag).is_err());
+        let tag = Tag::Compound(HashMap::from([("color".into(), Tag::String("red".into()))]));
+        assert!(TextComponent::try_from(&tag).is_err());
+    }
+
+    #[test]
+    fn content_types() {
+        // {selector: "@p"}
+        let mut data = vec![10, 8, 0, 8];
+        data.extend(b"selector");
+        data.extend([0, 2, b'@', b'p', 0]);
+        assert_eq!(round_trip_bytes(&data), TextComponent::selector("@p"));
+
+        let components = [
+            TextComponent::score("@s", "deaths"),
+            TextComponent::new(Content::Selector {
+                selector: "@a".into(),
+                separator: Some(Box::new(TextComponent::text(" | "))),
+            }),
+            TextComponent::new(Content::Nbt {
+                nbt: "Items[0].id".into(),
+                interpret: Some(false),
+                separator: None,
+                source: NbtSource::Block {
+                    block: "~ ~-1 ~".into(),
+                },
+            }),
+            TextComponent::new(Content::Nbt {
+                nbt: "quest".into(),
+                interpret: None,
+                separator: None,
+                source: NbtSource::Storage {
+                    storage: ResourceLocation::from("ocelot", "quests").unwrap(),
+                },
+            }),
+            TextComponent::new(Content::Object {
+                object: ObjectInfo::Atlas {
+                    atlas: None,
+                    sprite: ResourceLocation::from_vanilla("block/stone").unwrap(),
+                },
+            }),
+            TextComponent::new(Content::Object {
+                object: ObjectInfo::Player {
+                    player: PlayerProfile {
+                        name: Some("Steve".into()),
+                        id: Some(Uuid::from_u128(0x0123_4567_89AB_CDEF_0123_4567_89AB_CDEF)),
+                        properties: vec![ProfileProperty {
+                            name: "textures".into(),
+                            value: "e30=".into(),
+                            signature: None,
+                        }],
+                    },
+                    hat: Some(false),
+                },
+            }),
+        ];
+        for component in components {
+            assert_eq!(round_trip(&Tag::from(&component)), component);
+        }
+
+        // An explicit type wins over the fields, and profiles may be just a name.
+        let tag = Tag::Compound(HashMap::from([
+            ("type".into(), Tag::String("object".into())),
+            ("text".into(), Tag::String("ignored".into())),
+            ("object".into(), Tag::String("player".into())),
+            ("player".into(), Tag::String("Alex".into())),
+        ]));
+        let Content::Object {
+            object: ObjectInfo::Player { player, .. },
+        } = TextComponent::try_from(&tag).unwrap().get_content().clone()
+        else {
+            panic!("Expected a player object!");
+        };
+        assert_eq!(player.name.as_deref(), Some("Alex"));
+
+        let tag = Tag::Compound(HashMap::from([("nbt".into(), Tag::String("a".into()))]));
+        assert!(TextComponent::try_from(&tag).is_err());
+    }
+
+    #[test]
+    fn item_and_entity_events() {
+        let uuid = Uuid::from_u128(0x0000_0001_0000_0002_FFFF_FFFF_0000_0004);
+        let component = TextComponent::text("Zombie")
+            .hover_event(HoverEvent::ShowEntity {
+                id: ResourceLocation::from_vanilla("zombie").unwrap(),
+                uuid,
+                name: Some(Box::new(TextComponent::text("Bob"))),
+            })
+            .click_event(ClickEvent::ShowDialog {
+                dialog: Dialog::Id(ResourceLocation::from_vanilla("server_links").unwrap()),
+            });
+        let tag = Tag::from(&component);
+        assert_eq!(round_trip(&tag), component);
+        let Tag::Compound(compound) = &tag else {
+            panic!("Expected a compound!");
+        };
+        let Tag::Compound(hover_event) = &compound["hover_event"] else {
+            panic!("Expected a compound!");
+        };
+        assert_eq!(hover_event["uuid"], Tag::IntArray(vec![1, 2, -1, 4]));
+
+        let component = TextComponent::text("Sword")
+            .hover_event(HoverEvent::ShowItem {
+                id: ResourceLocation::from_vanilla("diamond_sword").unwrap(),
+                count: 1,
+                components: Some(serde_json::json!({"minecraft:damage": 5})),
+            })
+            .click_event(ClickEvent::ShowDialog {
+                dialog: Dialog::Inline(serde_json::json!({
+                    "type": "minecraft:notice",
+                    "title": "Hi",
+                })),
+            });
+        assert_eq!(round_trip(&Tag::from(&component)), component);
+
+        // The count defaults to one
+        let tag = Tag::Compound(HashMap::from([
+            ("action".into(), Tag::String("show_item".into())),
+            ("id".into(), Tag::String("minecraft:stone".into())),
+        ]));
+        assert!(matches!(
+            decode_hover_event(&tag).unwrap(),
+            HoverEvent::ShowItem { count: 1, .. }
+        ));
+    }
+}
@@ -1,19 +1,64 @@
 use std::io::{self, Read, Write};
 
-pub struct PacketBuffer<'a> {
-    cursor: &'a [u8],
+use bytes::Bytes;
+
+use crate::error::ProtocolError;
+
+/// Reads a packet out of [`Bytes`], so shared codec types can slice it instead of copying.
+pub struct PacketBuffer {
+    data: Bytes,
+    position: usize,
+    lenient: bool,
 }
-impl<'a> PacketBuffer<'a> {
-    pub fn new(data: &'a [u8]) -> Self {
-        Self { cursor: data }
+impl PacketBuffer {
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self {
+            data: data.into(),
+            position: 0,
+            lenient: false,
+        }
+    }
+    /// Lenient buffers accept bytes a packet left unread instead of failing, see [`Self::remaining`].
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+    pub fn remaining_slice(&self) -> &[u8] {
+        &self.data[self.position..]
+    }
+    /// The next `length` bytes, sharing the memory of the packet.
+    pub fn split_to(&mut self, length: usize) -> Result<Bytes, ProtocolError> {
+        if length > self.remaining() {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        let bytes = self.data.slice(self.position..self.position + length);
+        self.position += length;
+        Ok(bytes)
+    }
+    pub fn split_remaining(&mut self) -> Bytes {
+        let bytes = self.data.slice(self.position..);
+        self.position = self.data.len();
+        bytes
     }
 }
-impl<'a> Read for PacketBuffer<'a> {
+impl Read for PacketBuffer {
     fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
-        self.cursor.read(buffer)
+        let read = self.remaining_slice().read(buffer)?;
+        self.position += read;
+        Ok(read)
     }
 }
 
+#[derive(Default)]
 pub struct PacketWriter {
     data: Vec<u8>,
 }
@@ -21,6 +66,11 @@ impl PacketWriter {
     pub fn new() -> Self {
         Self { data: Vec::new() }
     }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(capacity),
+        }
+    }
     pub fn build(self) -> Vec<u8> {
         self.data
     }
@@ -33,3 +83,66 @@ impl Write for PacketWriter {
         self.data.flush()
     }
 }
+
+/// Reusable scratch buffers, so bursts of large packets don't allocate for each of them.
+#[derive(Default)]
+pub struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+}
+impl BufferPool {
+    const MAX_BUFFERS: usize = 4;
+    /// Bigger buffers are dropped, so a single huge packet doesn't keep its memory alive.
+    const MAX_CAPACITY: usize = 4 * 1024 * 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// An empty buffer with room for at least `capacity` bytes.
+    pub fn take(&mut self, capacity: usize) -> Vec<u8> {
+        let mut buffer = self.buffers.pop().unwrap_or_default();
+        buffer.reserve(capacity);
+        buffer
+    }
+    pub fn put(&mut self, mut buffer: Vec<u8>) {
+        if self.buffers.len() < Self::MAX_BUFFERS && buffer.capacity() <= Self::MAX_CAPACITY {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::MinecraftCodec;
+
+    #[test]
+    fn shared_slices() {
+        let data = Bytes::from_static(&[1, 2, 3, 4, 5]);
+        let mut buffer = PacketBuffer::new(data.clone());
+        assert_eq!(u8::decode(&mut buffer).unwrap(), 1);
+        let slice = buffer.split_to(2).unwrap();
+        assert_eq!(slice, [2, 3][..]);
+        assert_eq!(slice.as_ptr(), data[1..].as_ptr());
+        assert_eq!((buffer.position(), buffer.remaining()), (3, 2));
+        assert!(buffer.split_to(3).is_err());
+        assert_eq!(buffer.split_remaining(), [4, 5][..]);
+        assert_eq!(buffer.remaining(), 0);
+    }
+
+    #[test]
+    fn buffer_pool() {
+        let mut pool = BufferPool::new();
+        let mut buffer = pool.take(1024);
+        buffer.extend_from_slice(&[1, 2, 3]);
+        let pointer = buffer.as_ptr();
+        pool.put(buffer);
+
+        let buffer = pool.take(16);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr(), pointer);
+        pool.put(buffer);
+        pool.put(Vec::with_capacity(BufferPool::MAX_CAPACITY + 1));
+        assert_eq!(pool.buffers.len(), 1);
+    }
+}
@@ -1,28 +1,77 @@
 use std::io::{self, Read, Write};
 
-use ocelot_types::{BoundedString, CustomType, ResourceLocation, VarInt, VarLong};
+use bytes::Bytes;
+use ocelot_nbt::{NamedTag, Tag};
+pub use ocelot_types::budget::DecodeBudget;
+use ocelot_types::{
+    BoundedString, CustomType, MAX_STRING_LENGTH, ResourceLocation, VarInt, VarLong,
+    text::TextComponent,
+};
 use serde::{Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
+use crate::{buffer::PacketBuffer, error::ProtocolError};
+
 pub trait MinecraftCodec: Sized {
+    /// Decoding reads everything left in the packet, so nothing can follow the value.
+    const READS_TO_END: bool = false;
+
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+    /// Number of bytes [`MinecraftCodec::encode`] writes.
+    ///
+    /// The default encodes into a counter, derived and built-in types compute it directly.
+    fn encoded_len(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        // Values that fail to encode fail again once they are actually written.
+        let _ = self.encode(&mut counter);
+        counter.0
+    }
+    /// Decodes with a fresh [`DecodeBudget`] of the default size.
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, ProtocolError> {
+        Self::decode_with_budget(reader, &mut DecodeBudget::default())
+    }
+    /// Decodes and charges everything that is allocated for it to `budget`.
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError>;
+    /// Decodes from the [`Bytes`] of a packet, which shared types slice instead of copying.
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Self::decode_with_budget(buffer, budget)
+    }
+}
+
+struct ByteCounter(usize);
+impl Write for ByteCounter {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.0 += buffer.len();
+        Ok(buffer.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 impl MinecraftCodec for bool {
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         writer.write_all(&[*self as u8])
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn encoded_len(&self) -> usize {
+        1
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
         let mut buffer = [0u8; 1];
         reader.read_exact(&mut buffer)?;
         match buffer[0] {
             0 => Ok(false),
             1 => Ok(true),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid boolean value!",
-            )),
+            value => Err(ProtocolError::InvalidBool(value)),
         }
     }
 }
@@ -33,7 +82,13 @@ macro_rules! number_codec {
             fn encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
                 writer.write_all(&self.to_be_bytes())
             }
-            fn decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+            fn encoded_len(&self) -> usize {
+                size_of::<Self>()
+            }
+            fn decode_with_budget<R: std::io::Read>(
+                reader: &mut R,
+                _budget: &mut DecodeBudget,
+            ) -> Result<Self, ProtocolError> {
                 let mut buffer = [0u8; size_of::<Self>()];
                 reader.read_exact(&mut buffer)?;
                 Ok(Self::from_be_bytes(buffer))
@@ -55,13 +110,39 @@ impl<const MAX: u64> MinecraftCodec for BoundedString<MAX> {
         VarInt(bytes.len() as i32).encode(writer)?;
         writer.write_all(bytes)
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let mut buffer = vec![0u8; VarInt::decode(reader)?.0 as usize];
+    fn encoded_len(&self) -> usize {
+        VarInt(self.0.len() as i32).encoded_len() + self.0.len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let length = VarInt::decode(reader)?.0;
+        if length < 0 {
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        let max = MAX.min(MAX_STRING_LENGTH) as usize;
+        // Every UTF-16 unit takes at most three bytes in UTF-8.
+        if length as usize > max * 3 {
+            return Err(ProtocolError::TooLong {
+                kind: "string",
+                length: length as usize,
+                max: max * 3,
+            });
+        }
+        budget.allocate(length as usize)?;
+        let mut buffer = vec![0u8; length as usize];
         reader.read_exact(&mut buffer)?;
-        Self::new(
-            String::from_utf8(buffer)
-                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
-        )
+        let string = String::from_utf8(buffer)?;
+        let utf16_length = string.encode_utf16().count();
+        if utf16_length > max {
+            return Err(ProtocolError::TooLong {
+                kind: "string",
+                length: utf16_length,
+                max,
+            });
+        }
+        Ok(Self(string))
     }
 }
 
@@ -69,11 +150,17 @@ impl MinecraftCodec for ResourceLocation {
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         BoundedString::<32767>::new(self.to_string())?.encode(writer)
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
-        BoundedString::<32767>::decode(reader)?
+    fn encoded_len(&self) -> usize {
+        let length = self.namespace().len() + 1 + self.path().len();
+        VarInt(length as i32).encoded_len() + length
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(BoundedString::<32767>::decode_with_budget(reader, budget)?
             .0
-            .try_into()
-            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+            .try_into()?)
     }
 }
 
@@ -81,8 +168,14 @@ impl MinecraftCodec for VarInt {
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         self.write_to(writer)
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
-        VarInt::read_from(reader)
+    fn encoded_len(&self) -> usize {
+        VarInt::encoded_len(self)
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(VarInt::read_from(reader)?)
     }
 }
 
@@ -90,18 +183,28 @@ impl MinecraftCodec for VarLong {
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         self.write_to(writer)
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
-        VarLong::read_from(reader)
+    fn encoded_len(&self) -> usize {
+        VarLong::encoded_len(self)
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(VarLong::read_from(reader)?)
     }
 }
 
-// Position
-
 impl MinecraftCodec for Uuid {
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         writer.write_all(&self.as_u128().to_be_bytes())
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn encoded_len(&self) -> usize {
+        16
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
         let mut buffer = [0u8; 16];
         reader.read_exact(&mut buffer)?;
         Ok(Uuid::from_u128(u128::from_be_bytes(buffer)))
@@ -109,6 +212,8 @@ impl MinecraftCodec for Uuid {
 }
 
 impl<T: MinecraftCodec> MinecraftCodec for Option<T> {
+    const READS_TO_END: bool = T::READS_TO_END;
+
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         match self {
             Some(value) => {
@@ -118,10 +223,28 @@ impl<T: MinecraftCodec> MinecraftCodec for Option<T> {
             None => false.encode(writer),
         }
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn encoded_len(&self) -> usize {
+        1 + self.as_ref().map_or(0, T::encoded_len)
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
         let exists = bool::decode(reader)?;
         if exists {
-            let value = T::decode(reader)?;
+            let value = T::decode_with_budget(reader, budget)?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let exists = bool::decode(buffer)?;
+        if exists {
+            let value = T::decode_shared(buffer, budget)?;
             Ok(Some(value))
         } else {
             Ok(None)
@@ -129,14 +252,339 @@ impl<T: MinecraftCodec> MinecraftCodec for Option<T> {
     }
 }
 
-impl MinecraftCodec for Vec<u8> {
+/// Everything until the end of the packet, so it can only be the last field.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RemainingBytes(pub Vec<u8>);
+impl MinecraftCodec for RemainingBytes {
+    const READS_TO_END: bool = true;
+
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
-        writer.write_all(self)
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.len()
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
         let mut buffer = Vec::new();
         reader.read_to_end(&mut buffer)?;
-        Ok(buffer)
+        budget.allocate(buffer.len())?;
+        Ok(Self(buffer))
+    }
+}
+
+/// Byte array with a VarInt length prefix of at most `MAX`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PrefixedBytes<const MAX: u64>(pub Vec<u8>);
+impl<const MAX: u64> MinecraftCodec for PrefixedBytes<MAX> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.0.len() > MAX as usize {
+            return Err(ProtocolError::TooLong {
+                kind: "byte array",
+                length: self.0.len(),
+                max: MAX as usize,
+            }
+            .into());
+        }
+        VarInt(self.0.len() as i32).encode(writer)?;
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        VarInt(self.0.len() as i32).encoded_len() + self.0.len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let length = VarInt::decode(reader)?.0;
+        if length < 0 {
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        if length as u64 > MAX {
+            return Err(ProtocolError::TooLong {
+                kind: "byte array",
+                length: length as usize,
+                max: MAX as usize,
+            });
+        }
+        budget.allocate(length as usize)?;
+        let mut buffer = vec![0u8; length as usize];
+        reader.read_exact(&mut buffer)?;
+        Ok(Self(buffer))
+    }
+}
+
+/// Byte array of exactly `N` bytes without a length prefix.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FixedBytes<const N: usize>(pub [u8; N]);
+impl<const N: usize> MinecraftCodec for FixedBytes<N> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        N
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let mut buffer = [0u8; N];
+        reader.read_exact(&mut buffer)?;
+        Ok(Self(buffer))
+    }
+}
+
+/// [`RemainingBytes`] that share the memory of the packet.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SharedRemainingBytes(pub Bytes);
+impl MinecraftCodec for SharedRemainingBytes {
+    const READS_TO_END: bool = true;
+
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(Self(
+            RemainingBytes::decode_with_budget(reader, budget)?.0.into(),
+        ))
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(Self(buffer.split_remaining()))
+    }
+}
+
+/// [`PrefixedBytes`] that share the memory of the packet.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SharedPrefixedBytes<const MAX: u64>(pub Bytes);
+impl<const MAX: u64> SharedPrefixedBytes<MAX> {
+    fn decode_length<R: Read>(reader: &mut R) -> Result<usize, ProtocolError> {
+        let length = VarInt::decode(reader)?.0;
+        if length < 0 {
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        if length as u64 > MAX {
+            return Err(ProtocolError::TooLong {
+                kind: "byte array",
+                length: length as usize,
+                max: MAX as usize,
+            });
+        }
+        Ok(length as usize)
+    }
+}
+impl<const MAX: u64> MinecraftCodec for SharedPrefixedBytes<MAX> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.0.len() > MAX as usize {
+            return Err(ProtocolError::TooLong {
+                kind: "byte array",
+                length: self.0.len(),
+                max: MAX as usize,
+            }
+            .into());
+        }
+        VarInt(self.0.len() as i32).encode(writer)?;
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        VarInt(self.0.len() as i32).encoded_len() + self.0.len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let length = Self::decode_length(reader)?;
+        budget.allocate(length)?;
+        let mut buffer = vec![0u8; length];
+        reader.read_exact(&mut buffer)?;
+        Ok(Self(buffer.into()))
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let length = Self::decode_length(buffer)?;
+        Ok(Self(buffer.split_to(length)?))
+    }
+}
+
+/// Already encoded network NBT, decoding only walks the tags to find where they end.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RawNbt(pub Vec<u8>);
+impl RawNbt {
+    const MAX_DEPTH: usize = Tag::MAX_DEPTH;
+
+    fn skip<R: Read>(reader: &mut R, length: usize) -> Result<(), ProtocolError> {
+        if io::copy(&mut reader.take(length as u64), &mut io::sink())? < length as u64 {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        Ok(())
+    }
+    fn skip_length<R: Read>(reader: &mut R, item_size: usize) -> Result<(), ProtocolError> {
+        let length = i32::decode(reader)?;
+        if length < 0 {
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        Self::skip(reader, length as usize * item_size)
+    }
+    fn skip_payload<R: Read>(
+        reader: &mut R,
+        tag_type: u8,
+        depth: usize,
+    ) -> Result<(), ProtocolError> {
+        if depth > Self::MAX_DEPTH {
+            return Err(ProtocolError::TooLong {
+                kind: "NBT depth",
+                length: depth,
+                max: Self::MAX_DEPTH,
+            });
+        }
+        match tag_type {
+            1 => Self::skip(reader, 1),
+            2 => Self::skip(reader, 2),
+            3 | 5 => Self::skip(reader, 4),
+            4 | 6 => Self::skip(reader, 8),
+            7 => Self::skip_length(reader, 1),
+            8 => {
+                let length = u16::decode(reader)?;
+                Self::skip(reader, length as usize)
+            }
+            9 => {
+                let item_type = u8::decode(reader)?;
+                let length = i32::decode(reader)?;
+                if length < 0 {
+                    return Err(ProtocolError::NegativeLength(length));
+                }
+                for _ in 0..length {
+                    Self::skip_payload(reader, item_type, depth + 1)?;
+                }
+                Ok(())
+            }
+            10 => loop {
+                let entry_type = u8::decode(reader)?;
+                if entry_type == 0 {
+                    return Ok(());
+                }
+                Self::skip_payload(reader, 8, depth)?;
+                Self::skip_payload(reader, entry_type, depth + 1)?;
+            },
+            11 => Self::skip_length(reader, 4),
+            12 => Self::skip_length(reader, 8),
+            tag_type => Err(ProtocolError::InvalidDiscriminant {
+                name: "NBT tag type",
+                value: tag_type as i64,
+            }),
+        }
+    }
+    fn skip_tag<R: Read>(reader: &mut R) -> Result<(), ProtocolError> {
+        match u8::decode(reader)? {
+            0 => Ok(()),
+            tag_type => Self::skip_payload(reader, tag_type, 0),
+        }
+    }
+}
+impl MinecraftCodec for RawNbt {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let mut recorder = Recorder {
+            reader,
+            data: Vec::new(),
+            budget,
+        };
+        RawNbt::skip_tag(&mut recorder)?;
+        Ok(Self(recorder.data))
+    }
+}
+
+/// Keeps a copy of everything read, charging it to the budget.
+struct Recorder<'a, R> {
+    reader: &'a mut R,
+    data: Vec<u8>,
+    budget: &'a mut DecodeBudget,
+}
+impl<R: Read> Read for Recorder<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buffer)?;
+        self.budget.allocate(read)?;
+        self.data.extend_from_slice(&buffer[..read]);
+        Ok(read)
+    }
+}
+
+/// [`RawNbt`] that shares the memory of the packet.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SharedRawNbt(pub Bytes);
+impl MinecraftCodec for SharedRawNbt {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(Self(RawNbt::decode_with_budget(reader, budget)?.0.into()))
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let mut reader = buffer.remaining_slice();
+        RawNbt::skip_tag(&mut reader)?;
+        let length = buffer.remaining() - reader.len();
+        Ok(Self(buffer.split_to(length)?))
+    }
+}
+
+/// Network NBT, whose root tag has no name.
+///
+/// Decoding takes at most [`Nbt::MAX_SIZE`] of the budget and stops at [`Tag::MAX_DEPTH`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct Nbt<T = Tag>(pub T);
+impl Nbt {
+    /// Vanilla's limit for NBT in a packet.
+    pub const MAX_SIZE: usize = 2 * 1024 * 1024;
+}
+impl MinecraftCodec for Nbt<Tag> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.tag_type().as_id().encode(writer)?;
+        self.0.encode_binary(writer)
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.0.binary_len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let limit = budget.remaining().min(Self::MAX_SIZE);
+        let mut nbt_budget = DecodeBudget::new(limit);
+        let tag = NamedTag::decode_binary_from_network_with_budget(reader, &mut nbt_budget)?;
+        budget.allocate(limit - nbt_budget.remaining())?;
+        match tag {
+            Some(NamedTag(_, tag)) => Ok(Self(tag)),
+            None => Err(ProtocolError::Invalid("empty NBT".into())),
+        }
     }
 }
 
@@ -145,10 +593,20 @@ impl<T: MinecraftCodec> PrefixedArray<T> {
     fn new(array: Vec<T>) -> Self {
         Self(array)
     }
-    fn decode_items<R: Read>(reader: &mut R, size: usize) -> io::Result<Self> {
-        let mut result = Vec::with_capacity(size as usize);
-        for _ in 0..size {
-            result.push(T::decode(reader)?);
+    fn decode_items<R: Read>(
+        reader: &mut R,
+        size: i32,
+        budget: &mut DecodeBudget,
+        decode_item: fn(&mut R, &mut DecodeBudget) -> Result<T, ProtocolError>,
+    ) -> Result<Self, ProtocolError> {
+        if size < 0 {
+            return Err(ProtocolError::NegativeLength(size));
+        }
+        let mut result = budget.vec_with_capacity(size as usize)?;
+        for i in 0..size {
+            result.push(
+                decode_item(reader, budget).map_err(|error| error.in_field(&format!("[{}]", i)))?,
+            );
         }
         Ok(Self(result))
     }
@@ -158,9 +616,22 @@ impl<T: MinecraftCodec> MinecraftCodec for PrefixedArray<T> {
         VarInt(self.0.len() as i32).encode(writer)?;
         self.0.iter().try_for_each(|value| value.encode(writer))
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+    fn encoded_len(&self) -> usize {
+        VarInt(self.0.len() as i32).encoded_len() + self.0.iter().map(T::encoded_len).sum::<usize>()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
         let size = VarInt::decode(reader)?.0;
-        Self::decode_items(reader, size as usize)
+        Self::decode_items(reader, size, budget, T::decode_with_budget)
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let size = VarInt::decode(buffer)?.0;
+        Self::decode_items(buffer, size, budget, T::decode_shared)
     }
 }
 
@@ -169,28 +640,55 @@ impl<T: MinecraftCodec, const MAX: u64> BoundedPrefixedArray<T, MAX> {
     pub fn new(array: Vec<T>) -> Self {
         Self(PrefixedArray::new(array))
     }
+    fn decode_size<R: Read>(reader: &mut R) -> Result<i32, ProtocolError> {
+        let size = VarInt::decode(reader)?.0;
+        if size > MAX as i32 {
+            return Err(ProtocolError::TooLong {
+                kind: "array",
+                length: size as usize,
+                max: MAX as usize,
+            });
+        }
+        Ok(size)
+    }
 }
 impl<T: MinecraftCodec, const MAX: u64> MinecraftCodec for BoundedPrefixedArray<T, MAX> {
     fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         if self.0.0.len() > MAX as usize {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Array is too long!",
-            ));
+            return Err(ProtocolError::TooLong {
+                kind: "array",
+                length: self.0.0.len(),
+                max: MAX as usize,
+            }
+            .into());
         }
         self.0.encode(writer)
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let size = VarInt::decode(reader)?.0;
-        if size > MAX as i32 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Array is too long!",
-            ));
-        }
-        Ok(Self(PrefixedArray::<T>::decode_items(
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let size = Self::decode_size(reader)?;
+        Ok(Self(PrefixedArray::decode_items(
             reader,
-            size as usize,
+            size,
+            budget,
+            T::decode_with_budget,
+        )?))
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let size = Self::decode_size(buffer)?;
+        Ok(Self(PrefixedArray::decode_items(
+            buffer,
+            size,
+            budget,
+            T::decode_shared,
         )?))
     }
 }
@@ -206,11 +704,38 @@ where
         let bounded_string = BoundedString::<32767>::new(json_string)?;
         bounded_string.encode(writer)
     }
-    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let bounded_string = BoundedString::<32767>::decode(reader)?;
-        let res = serde_json::from_str(&bounded_string.0)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        Ok(Json(res))
+    fn encoded_len(&self) -> usize {
+        // Counted without building the string, values that fail to serialize fail again in `encode`.
+        let mut counter = ByteCounter(0);
+        let _ = serde_json::to_writer(&mut counter, &self.0);
+        VarInt(counter.0 as i32).encoded_len() + counter.0
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let bounded_string = BoundedString::<32767>::decode_with_budget(reader, budget)?;
+        Ok(Json(serde_json::from_str(&bounded_string.0)?))
+    }
+}
+
+/// Text component sent as network NBT, plain text is shortened to a string tag like vanilla does.
+pub struct NbtText(pub TextComponent);
+impl MinecraftCodec for NbtText {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        Nbt(Tag::from(&self.0)).encode(writer)
+    }
+    fn encoded_len(&self) -> usize {
+        Nbt(Tag::from(&self.0)).encoded_len()
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let Nbt(tag) = Nbt::decode_with_budget(reader, budget)?;
+        TextComponent::try_from(&tag)
+            .map(Self)
+            .map_err(|error| ProtocolError::Invalid(format!("invalid text component ({})", error)))
     }
 }
 
@@ -230,9 +755,289 @@ macro_rules! bitfield {
             fn encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
                 self.bits().encode(writer)
             }
-            fn decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+            fn encoded_len(&self) -> usize {
+                size_of::<$type>()
+            }
+            fn decode_with_budget<R: std::io::Read>(
+                reader: &mut R,
+                _budget: &mut $crate::codec::DecodeBudget,
+            ) -> Result<Self, $crate::error::ProtocolError> {
                 Ok(Self::from_bits_truncate(<$type>::decode(reader)?))
             }
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ocelot_macros::MinecraftCodec;
+
+    use super::*;
+
+    #[test]
+    fn hostile_lengths() {
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+        assert!(matches!(
+            PrefixedArray::<i64>::decode(&mut &data[..]),
+            Err(ProtocolError::NegativeLength(-1))
+        ));
+        // Claims 2^28 longs, but the budget runs out before anything is allocated.
+        let data = [0x80, 0x80, 0x80, 0x80, 0x01];
+        assert!(matches!(
+            PrefixedArray::<i64>::decode(&mut &data[..]),
+            Err(ProtocolError::BudgetExceeded(_))
+        ));
+        let error = PrefixedArray::<PrefixedArray<i64>>::decode(
+            &mut &[0x01, 0x80, 0x80, 0x80, 0x80, 0x01][..],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string().split(':').next(), Some("[0]"));
+
+        let data = [0xFF, 0xFF, 0x01];
+        assert!(matches!(
+            BoundedString::<16>::decode(&mut &data[..]),
+            Err(ProtocolError::TooLong { max: 48, .. })
+        ));
+        let data = [0x04, b'a', b'b', b'c', b'd'];
+        let mut budget = DecodeBudget::new(3);
+        assert!(BoundedString::<16>::decode_with_budget(&mut &data[..], &mut budget).is_err());
+    }
+
+    #[test]
+    fn byte_arrays() {
+        let mut data = Vec::new();
+        PrefixedBytes::<4>(vec![1, 2, 3]).encode(&mut data).unwrap();
+        FixedBytes([4, 5]).encode(&mut data).unwrap();
+        RemainingBytes(vec![6, 7]).encode(&mut data).unwrap();
+        assert_eq!(data, [3, 1, 2, 3, 4, 5, 6, 7]);
+
+        let mut reader = &data[..];
+        assert_eq!(
+            PrefixedBytes::<4>::decode(&mut reader).unwrap().0,
+            [1, 2, 3]
+        );
+        assert_eq!(FixedBytes::<2>::decode(&mut reader).unwrap().0, [4, 5]);
+        assert_eq!(RemainingBytes::decode(&mut reader).unwrap().0, [6, 7]);
+
+        let mut buffer = PacketBuffer::new(data.clone());
+        let budget = &mut DecodeBudget::new(0);
+        let shared = SharedPrefixedBytes::<4>::decode_shared(&mut buffer, budget).unwrap();
+        assert_eq!(shared.0, [1, 2, 3][..]);
+        buffer.split_to(2).unwrap();
+        let shared = SharedRemainingBytes::decode_shared(&mut buffer, budget).unwrap();
+        assert_eq!(shared.0, [6, 7][..]);
+
+        assert!(PrefixedBytes::<2>::decode(&mut &data[..]).is_err());
+        assert!(
+            PrefixedBytes::<2>(vec![0; 3])
+                .encode(&mut Vec::new())
+                .is_err()
+        );
+        assert!(matches!(
+            FixedBytes::<9>::decode(&mut &data[..]),
+            Err(ProtocolError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn raw_nbt() {
+        // {list: [1b, 2b], name: "ab"} followed by a trailing byte
+        let nbt = [
+            10, 9, 0, 4, b'l', b'i', b's', b't', 1, 0, 0, 0, 2, 1, 2, 8, 0, 4, b'n', b'a', b'm',
+            b'e', 0, 2, b'a', b'b', 0,
+        ];
+        let data = [&nbt[..], &[0xFF]].concat();
+        let mut reader = &data[..];
+        assert_eq!(RawNbt::decode(&mut reader).unwrap().0, nbt);
+        assert_eq!(reader, [0xFF]);
+
+        let mut buffer = PacketBuffer::new(data.clone());
+        let shared = SharedRawNbt::decode_shared(&mut buffer, &mut DecodeBudget::new(0)).unwrap();
+        assert_eq!(shared.0, nbt[..]);
+        assert_eq!(buffer.remaining(), 1);
+
+        assert_eq!(RawNbt::decode(&mut &[0][..]).unwrap().0, [0]);
+        assert!(RawNbt::decode(&mut &nbt[..nbt.len() - 1]).is_err());
+
+        for registry in ocelot_data::registry::SYNCED_REGISTRIES {
+            for entry in registry.entries {
+                let mut reader = entry.nbt_bytes;
+                assert_eq!(RawNbt::decode(&mut reader).unwrap().0, entry.nbt_bytes);
+                assert!(reader.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn nbt() {
+        let nbt = Nbt(Tag::Compound(HashMap::from([("a".into(), Tag::Byte(1))])));
+        let data = [10, 1, 0, 1, b'a', 1, 0];
+        let mut encoded = Vec::new();
+        nbt.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, data);
+        assert_eq!(nbt.encoded_len(), data.len());
+        assert_eq!(Nbt::decode(&mut &data[..]).unwrap(), nbt);
+        assert!(Nbt::decode(&mut &[0][..]).is_err());
+
+        // A byte array claiming 3 MiB fails before anything is read
+        let data = [7, 0x00, 0x30, 0x00, 0x00];
+        assert!(matches!(
+            Nbt::decode(&mut &data[..]),
+            Err(ProtocolError::BudgetExceeded(_))
+        ));
+
+        for registry in ocelot_data::registry::SYNCED_REGISTRIES {
+            for entry in registry.entries {
+                let mut reader = entry.nbt_bytes;
+                let nbt = Nbt::decode(&mut reader).unwrap();
+                assert!(reader.is_empty());
+                assert_eq!(nbt.encoded_len(), entry.nbt_bytes.len());
+            }
+        }
+    }
+
+    #[test]
+    fn nbt_text() {
+        let data = [8, 0, 4, b'B', b'y', b'e', b'!'];
+        let text = NbtText::decode(&mut &data[..]).unwrap();
+        assert_eq!(text.0, TextComponent::text("Bye!"));
+        let mut encoded = Vec::new();
+        text.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, data);
+
+        // {color: "red"} has no content
+        let data = [
+            10, 8, 0, 5, b'c', b'o', b'l', b'o', b'r', 0, 3, b'r', b'e', b'd', 0,
+        ];
+        assert!(matches!(
+            NbtText::decode(&mut &data[..]),
+            Err(ProtocolError::Invalid(_))
+        ));
+
+        // Painting titles and authors are text components from vanilla's registry data.
+        let registry = ocelot_data::registry::SYNCED_REGISTRIES
+            .iter()
+            .find(|registry| registry.registry_id == "minecraft:painting_variant")
+            .unwrap();
+        for entry in registry.entries {
+            let Nbt(Tag::Compound(painting)) = Nbt::decode(&mut &entry.nbt_bytes[..]).unwrap()
+            else {
+                panic!("Expected a compound!");
+            };
+            for key in ["title", "author"] {
+                let Some(tag) = painting.get(key) else {
+                    continue;
+                };
+                let text = TextComponent::try_from(tag).unwrap();
+                assert_eq!(&Tag::from(&text), tag);
+                let text = NbtText(text);
+                let mut encoded = Vec::new();
+                text.encode(&mut encoded).unwrap();
+                assert_eq!(text.encoded_len(), encoded.len());
+            }
+        }
+    }
+
+    #[derive(MinecraftCodec, PartialEq, Debug)]
+    #[codec(tag = VarInt)]
+    enum Shape {
+        #[codec(id = 0)]
+        Empty,
+        #[codec(id = 1)]
+        Point(i8, i8),
+        #[codec(id = 2)]
+        Circle {
+            radius: u8,
+            #[codec(if = "*radius > 1")]
+            label: Option<u16>,
+        },
+    }
+
+    #[test]
+    fn derived_enums() {
+        let shapes = [
+            (Shape::Empty, vec![0]),
+            (Shape::Point(1, -1), vec![1, 1, 0xFF]),
+            (
+                Shape::Circle {
+                    radius: 2,
+                    label: Some(7),
+                },
+                vec![2, 2, 0, 7],
+            ),
+            (
+                Shape::Circle {
+                    radius: 1,
+                    label: None,
+                },
+                vec![2, 1],
+            ),
+        ];
+        for (shape, data) in shapes {
+            let mut encoded = Vec::new();
+            shape.encode(&mut encoded).unwrap();
+            assert_eq!(encoded, data);
+            assert_eq!(shape.encoded_len(), data.len());
+            assert_eq!(Shape::decode(&mut &data[..]).unwrap(), shape);
+            let mut buffer = PacketBuffer::new(data);
+            let decoded = Shape::decode_shared(&mut buffer, &mut DecodeBudget::default()).unwrap();
+            assert_eq!(decoded, shape);
+        }
+        assert_eq!(Shape::Point(0, 0).to_string(), "Point");
+
+        let mismatch = Shape::Circle {
+            radius: 1,
+            label: Some(7),
+        };
+        assert!(mismatch.encode(&mut Vec::new()).is_err());
+        let error = Shape::decode(&mut &[3][..]).err().unwrap();
+        assert_eq!(error.to_string(), "invalid Shape discriminant (3)");
+        let error = Shape::decode(&mut &[2, 2, 0][..]).err().unwrap();
+        assert_eq!(error.to_string(), "label: unexpected end of packet");
+    }
+
+    #[derive(MinecraftCodec, PartialEq, Debug)]
+    #[codec(via = u8)]
+    #[repr(u8)]
+    enum Mode {
+        CommandsOnly = 0,
+        RelativeX = 1,
+        HTTPServer = 2,
+        #[codec(other)]
+        Unknown(u8),
+    }
+
+    #[derive(MinecraftCodec, PartialEq, Debug)]
+    #[codec(via = VarInt)]
+    enum Strict {
+        First = 1,
+        Second = 2,
+    }
+
+    #[test]
+    fn unknown_variants() {
+        assert_eq!(Mode::decode(&mut &[1][..]).unwrap(), Mode::RelativeX);
+        assert_eq!(Mode::decode(&mut &[9][..]).unwrap(), Mode::Unknown(9));
+        let mut data = Vec::new();
+        Mode::Unknown(9).encode(&mut data).unwrap();
+        assert_eq!(data, [9]);
+        assert_eq!(Mode::Unknown(9).encoded_len(), 1);
+
+        assert_eq!(Mode::from(2), Mode::HTTPServer);
+        assert_eq!(Mode::from(7), Mode::Unknown(7));
+        assert_eq!(u8::from(Mode::Unknown(7)), 7);
+        assert_eq!(u8::from(Mode::HTTPServer), 2);
+        assert_eq!(Mode::ALL.len(), 3);
+        assert_eq!(Strict::ALL, [Strict::First, Strict::Second]);
+        assert_eq!(Strict::try_from(2).unwrap(), Strict::Second);
+        assert!(Strict::try_from(3).is_err());
+        assert_eq!(i32::from(Strict::First), 1);
+
+        let names: Vec<_> = Mode::ALL.iter().map(ToString::to_string).collect();
+        assert_eq!(names, ["Commands Only", "Relative X", "HTTP Server"]);
+        assert_eq!(Mode::Unknown(7).to_string(), "Unknown (7)");
+    }
+}
@@ -0,0 +1,111 @@
+use std::io::{self, Read, Write};
+
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use ocelot_types::VarInt;
+
+use crate::codec::MinecraftCodec;
+
+// see https://minecraft.wiki/w/Java_Edition_protocol/Packets#With_compression for specs
+
+/// Largest uncompressed packet a client may announce, same as vanilla.
+pub const MAX_DECOMPRESSED_SIZE: usize = 8 * 1024 * 1024;
+
+/// Turns a serialized packet (ID + data) into the Data Length + body part of a compressed frame.
+pub fn compress(packet: &[u8], threshold: usize) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    compress_into(packet, threshold, &mut body)?;
+    Ok(body)
+}
+
+/// Like [`compress`], but appends to `body` so its buffer can be reused.
+pub fn compress_into(packet: &[u8], threshold: usize, body: &mut Vec<u8>) -> io::Result<()> {
+    if packet.len() < threshold {
+        VarInt(0).encode(body)?;
+        body.extend_from_slice(packet);
+        return Ok(());
+    }
+    VarInt(packet.len() as i32).encode(body)?;
+    let mut encoder = ZlibEncoder::new(body, Compression::default());
+    encoder.write_all(packet)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Reverses [`compress`], rejecting frames a vanilla server would consider badly compressed.
+pub fn decompress(mut body: &[u8], threshold: usize) -> io::Result<Vec<u8>> {
+    let data_length = VarInt::decode(&mut body)?.0;
+    if data_length == 0 {
+        return Ok(body.to_vec());
+    }
+    if data_length < 0 || (data_length as usize) < threshold {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Badly compressed packet - size of {} is below server threshold of {}",
+                data_length, threshold
+            ),
+        ));
+    }
+    let data_length = data_length as usize;
+    if data_length > MAX_DECOMPRESSED_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Badly compressed packet - size of {} is larger than protocol maximum of {}",
+                data_length, MAX_DECOMPRESSED_SIZE
+            ),
+        ));
+    }
+    let mut packet = Vec::with_capacity(data_length);
+    ZlibDecoder::new(body)
+        .take(data_length as u64)
+        .read_to_end(&mut packet)?;
+    if packet.len() != data_length {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Badly compressed packet - size of {} does not match data length of {}",
+                packet.len(),
+                data_length
+            ),
+        ));
+    }
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn below_threshold_is_uncompressed() {
+        let packet = [0x01, 0x02, 0x03];
+        let body = compress(&packet, 256).unwrap();
+        assert_eq!(body, [0x00, 0x01, 0x02, 0x03]);
+        assert_eq!(decompress(&body, 256).unwrap(), packet);
+    }
+
+    #[test]
+    fn round_trip_above_threshold() {
+        let packet = vec![0x2A; 1024];
+        let body = compress(&packet, 256).unwrap();
+        assert_eq!(body[..2], [0x80, 0x08]);
+        assert!(body.len() < packet.len());
+        assert_eq!(decompress(&body, 256).unwrap(), packet);
+    }
+
+    #[test]
+    fn reject_below_threshold() {
+        let body = compress(&[0x2A; 64], 64).unwrap();
+        assert!(decompress(&body, 256).is_err());
+    }
+
+    #[test]
+    fn reject_too_large() {
+        let mut body = Vec::new();
+        VarInt(MAX_DECOMPRESSED_SIZE as i32 + 1)
+            .encode(&mut body)
+            .unwrap();
+        assert!(decompress(&body, 256).is_err());
+    }
+}
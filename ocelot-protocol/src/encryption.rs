@@ -0,0 +1,89 @@
+use std::io;
+
+use openssl::symm::{Cipher, Crypter, Mode};
+
+/// One direction of the AES-128-CFB8 stream cipher, keyed and IV'd with the shared secret.
+pub struct Cfb8 {
+    crypter: Crypter,
+    scratch: Vec<u8>,
+}
+impl Cfb8 {
+    pub fn new(mode: Mode, shared_secret: &[u8]) -> io::Result<Self> {
+        let mut crypter = Crypter::new(
+            Cipher::aes_128_cfb8(),
+            mode,
+            shared_secret,
+            Some(shared_secret),
+        )?;
+        crypter.pad(false);
+        Ok(Self {
+            crypter,
+            scratch: Vec::new(),
+        })
+    }
+    pub fn apply(&mut self, data: &mut [u8]) {
+        // CFB8 has a block size of one, so the output is always as long as the input.
+        self.scratch.resize(data.len() + 1, 0);
+        let written = self
+            .crypter
+            .update(data, &mut self.scratch)
+            .expect("CFB8 can't fail on update");
+        debug_assert_eq!(written, data.len());
+        data.copy_from_slice(&self.scratch[..data.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NIST SP 800-38A, F.3.7 CFB8-AES128
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    const PLAINTEXT: [u8; 18] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d,
+    ];
+    const CIPHERTEXT: [u8; 18] = [
+        0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a,
+        0x4f, 0x32, 0xb9,
+    ];
+
+    fn nist_cipher(mode: Mode) -> Cfb8 {
+        let mut crypter = Crypter::new(Cipher::aes_128_cfb8(), mode, &KEY, Some(&IV)).unwrap();
+        crypter.pad(false);
+        Cfb8 {
+            crypter,
+            scratch: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn nist_vector() {
+        let mut data = PLAINTEXT;
+        nist_cipher(Mode::Encrypt).apply(&mut data);
+        assert_eq!(data, CIPHERTEXT);
+        nist_cipher(Mode::Decrypt).apply(&mut data);
+        assert_eq!(data, PLAINTEXT);
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        let mut encryptor = nist_cipher(Mode::Encrypt);
+        let data: Vec<u8> = PLAINTEXT
+            .iter()
+            .flat_map(|byte| {
+                let mut byte = [*byte];
+                encryptor.apply(&mut byte);
+                byte
+            })
+            .collect();
+        assert_eq!(data, CIPHERTEXT);
+    }
+}
@@ -0,0 +1,139 @@
+use std::{io, string::FromUtf8Error};
+
+use ocelot_types::{ResourceLocationError, budget::BudgetExceeded};
+use thiserror::Error;
+
+/// Why a packet or one of its fields couldn't be decoded.
+#[derive(Error, Debug)]
+pub enum ProtocolError {
+    #[error("unexpected end of packet")]
+    UnexpectedEof,
+    #[error("{kind} too long ({length} > {max})")]
+    TooLong {
+        kind: &'static str,
+        length: usize,
+        max: usize,
+    },
+    #[error("negative length ({0})")]
+    NegativeLength(i32),
+    #[error("invalid boolean ({0})")]
+    InvalidBool(u8),
+    #[error("invalid {name} discriminant ({value})")]
+    InvalidDiscriminant { name: &'static str, value: i64 },
+    #[error("invalid UTF-8 ({0})")]
+    InvalidUtf8(#[from] FromUtf8Error),
+    #[error("invalid resource location ({0})")]
+    InvalidResourceLocation(#[from] ResourceLocationError),
+    #[error("invalid JSON ({0})")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error(transparent)]
+    BudgetExceeded(#[from] BudgetExceeded),
+    #[error("{0} trailing bytes")]
+    TrailingBytes(usize),
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Io(io::Error),
+    #[error("{path}: {source}")]
+    Field {
+        path: String,
+        source: Box<ProtocolError>,
+    },
+}
+impl ProtocolError {
+    /// Prefixes the path of the failed field with `field`, e.g. a packet name, a field name or an index.
+    pub fn in_field(self, field: &str) -> Self {
+        match self {
+            Self::Field { path, source } => {
+                let separator = if path.starts_with('[') { "" } else { "." };
+                Self::Field {
+                    path: format!("{}{}{}", field, separator, path),
+                    source,
+                }
+            }
+            error => Self::Field {
+                path: field.to_string(),
+                source: Box::new(error),
+            },
+        }
+    }
+    /// The error without the field context.
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::Field { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+}
+impl From<io::Error> for ProtocolError {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            return Self::UnexpectedEof;
+        }
+        if error.get_ref().is_some_and(|inner| inner.is::<Self>()) {
+            return *error.into_inner().unwrap().downcast::<Self>().unwrap();
+        }
+        if error
+            .get_ref()
+            .is_some_and(|inner| inner.is::<BudgetExceeded>())
+        {
+            return Self::BudgetExceeded(
+                *error
+                    .into_inner()
+                    .unwrap()
+                    .downcast::<BudgetExceeded>()
+                    .unwrap(),
+            );
+        }
+        Self::Io(error)
+    }
+}
+impl From<ProtocolError> for io::Error {
+    fn from(error: ProtocolError) -> Self {
+        match error {
+            ProtocolError::Io(error) => error,
+            ProtocolError::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, error),
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_path() {
+        let error = ProtocolError::TooLong {
+            kind: "string",
+            length: 17,
+            max: 16,
+        }
+        .in_field("namespace")
+        .in_field("[2]")
+        .in_field("known_packs")
+        .in_field("ServerboundConfiguration::KnownPacks");
+        assert_eq!(
+            error.to_string(),
+            "ServerboundConfiguration::KnownPacks.known_packs[2].namespace: string too long (17 > 16)"
+        );
+        assert!(matches!(
+            error.root_cause(),
+            ProtocolError::TooLong { length: 17, .. }
+        ));
+    }
+
+    #[test]
+    fn io_round_trip() {
+        let error: io::Error = ProtocolError::InvalidBool(2).in_field("flag").into();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = ProtocolError::from(error);
+        assert_eq!(error.to_string(), "flag: invalid boolean (2)");
+
+        let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert!(matches!(
+            ProtocolError::from(eof),
+            ProtocolError::UnexpectedEof
+        ));
+    }
+}
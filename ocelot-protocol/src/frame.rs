@@ -0,0 +1,538 @@
+use std::io;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use ocelot_types::{CONTINUE_BITS, SEGMENT_BITS, VarInt};
+use openssl::symm::Mode;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    buffer::{BufferPool, PacketBuffer},
+    codec::MinecraftCodec,
+    compression,
+    encryption::Cfb8,
+    packet::MinecraftPacket,
+};
+
+// see https://minecraft.wiki/w/Java_Edition_protocol/Packets#Packet_format for specs
+
+/// Vanilla never reads more than three bytes for the length of a frame.
+pub const MAX_FRAME_LENGTH_BYTES: usize = 3;
+/// Largest frame that fits into a three byte length prefix.
+pub const MAX_FRAME_SIZE: usize = (1 << (7 * MAX_FRAME_LENGTH_BYTES)) - 1;
+
+/// A received packet, split into its ID and the still undecoded data.
+pub struct Frame {
+    pub id: i32,
+    pub body: Bytes,
+}
+impl Frame {
+    pub fn buffer(&self) -> PacketBuffer {
+        PacketBuffer::new(self.body.clone())
+    }
+}
+
+/// Splits the byte stream into [`Frame`]s and frames serialized packets (ID + data).
+///
+/// Compression and encryption are off until they are enabled, and only affect frames after that.
+#[derive(Default)]
+pub struct PacketCodec {
+    compression_threshold: Option<usize>,
+    decryptor: Option<Cfb8>,
+    encryptor: Option<Cfb8>,
+    // Bytes at the front of the read buffer that were already decrypted.
+    decrypted: usize,
+    pool: BufferPool,
+}
+impl PacketCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn enable_compression(&mut self, threshold: usize) {
+        self.compression_threshold = Some(threshold);
+    }
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.compression_threshold
+    }
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> io::Result<()> {
+        self.decryptor = Some(Cfb8::new(Mode::Decrypt, shared_secret)?);
+        self.encryptor = Some(Cfb8::new(Mode::Encrypt, shared_secret)?);
+        // Everything that wasn't split into frames yet was sent encrypted.
+        self.decrypted = 0;
+        Ok(())
+    }
+    pub fn is_encrypted(&self) -> bool {
+        self.encryptor.is_some() || self.decryptor.is_some()
+    }
+    /// Splits into a codec that keeps the decoding state and one that keeps the encoding state,
+    /// so each direction can be driven on its own.
+    pub fn split(self) -> (Self, Self) {
+        let decoder = Self {
+            compression_threshold: self.compression_threshold,
+            decryptor: self.decryptor,
+            encryptor: None,
+            decrypted: self.decrypted,
+            pool: BufferPool::new(),
+        };
+        let encoder = Self {
+            compression_threshold: self.compression_threshold,
+            decryptor: None,
+            encryptor: self.encryptor,
+            decrypted: 0,
+            pool: self.pool,
+        };
+        (decoder, encoder)
+    }
+    /// Frames a packet straight into `dst`, so packets below the compression threshold are written
+    /// without any intermediate buffer.
+    pub fn encode_packet<P: MinecraftPacket>(
+        &mut self,
+        packet: &P,
+        id: i32,
+        dst: &mut BytesMut,
+    ) -> io::Result<()> {
+        let start = dst.len();
+        if let Err(error) = self.write_packet(packet, id, dst) {
+            dst.truncate(start);
+            return Err(error);
+        }
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.apply(&mut dst[start..]);
+        }
+        Ok(())
+    }
+    fn write_packet<P: MinecraftPacket>(
+        &mut self,
+        packet: &P,
+        id: i32,
+        dst: &mut BytesMut,
+    ) -> io::Result<()> {
+        // The packet is encoded behind room for the longest header, which is then filled in from the
+        // length that was actually written, so a wrong `encoded_len` can't corrupt the stream.
+        let start = dst.len();
+        let compressed = self.compression_threshold.is_some();
+        let header_length = MAX_FRAME_LENGTH_BYTES + usize::from(compressed);
+        dst.resize(start + header_length, 0);
+        packet.encode_with_id(id, &mut (&mut *dst).writer())?;
+        let data_length = dst.len() - start - header_length;
+        if let Some(threshold) = self.compression_threshold
+            && data_length >= threshold
+        {
+            let mut body = self.pool.take(data_length);
+            let result =
+                compression::compress_into(&dst[start + header_length..], threshold, &mut body);
+            dst.truncate(start);
+            let result = result.and_then(|_| write_frame(&body, dst));
+            self.pool.put(body);
+            return result;
+        }
+        // An uncompressed packet only gets a zero Data Length in front.
+        let frame_length = data_length + usize::from(compressed);
+        check_frame_size(frame_length)?;
+        let mut header = [0; MAX_FRAME_LENGTH_BYTES + 1];
+        let mut writer = &mut header[..];
+        VarInt(frame_length as i32).encode(&mut writer)?;
+        if compressed {
+            VarInt(0).encode(&mut writer)?;
+        }
+        let written = MAX_FRAME_LENGTH_BYTES + 1 - writer.len();
+        let offset = header_length - written;
+        dst[start + offset..start + header_length].copy_from_slice(&header[..written]);
+        if offset > 0 {
+            dst.copy_within(start + offset.., start);
+            dst.truncate(dst.len() - offset);
+        }
+        Ok(())
+    }
+}
+
+fn check_frame_size(length: usize) -> io::Result<()> {
+    if length > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Packet too large: size {} is over {}",
+                length, MAX_FRAME_SIZE
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn write_frame(data: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+    check_frame_size(data.len())?;
+    let prefix = VarInt(data.len() as i32);
+    dst.reserve(prefix.encoded_len() + data.len());
+    prefix.encode(&mut dst.writer())?;
+    dst.extend_from_slice(data);
+    Ok(())
+}
+impl Decoder for PacketCodec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Frame>> {
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.apply(&mut src[self.decrypted..]);
+            self.decrypted = src.len();
+        }
+        let mut length = 0;
+        let mut prefix_length = None;
+        for (i, byte) in src.iter().take(MAX_FRAME_LENGTH_BYTES).enumerate() {
+            length |= ((byte & SEGMENT_BITS) as usize) << (7 * i);
+            if byte & CONTINUE_BITS == 0 {
+                prefix_length = Some(i + 1);
+                break;
+            }
+        }
+        let Some(prefix_length) = prefix_length else {
+            if src.len() >= MAX_FRAME_LENGTH_BYTES {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Frame length is too big!",
+                ));
+            }
+            return Ok(None);
+        };
+        if src.len() < prefix_length + length {
+            src.reserve(prefix_length + length - src.len());
+            return Ok(None);
+        }
+        src.advance(prefix_length);
+        let mut body = src.split_to(length).freeze();
+        if self.decryptor.is_some() {
+            self.decrypted -= prefix_length + length;
+        }
+        if let Some(threshold) = self.compression_threshold {
+            body = compression::decompress(&body, threshold)?.into();
+        }
+        let mut reader = &body[..];
+        let id = VarInt::decode(&mut reader)?.0;
+        let id_length = body.len() - reader.len();
+        body.advance(id_length);
+        Ok(Some(Frame { id, body }))
+    }
+}
+impl Encoder<&[u8]> for PacketCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, packet: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        let start = dst.len();
+        let result = match self.compression_threshold {
+            Some(threshold) => {
+                let mut body = self.pool.take(packet.len() + 1);
+                let result = compression::compress_into(packet, threshold, &mut body)
+                    .and_then(|_| write_frame(&body, dst));
+                self.pool.put(body);
+                result
+            }
+            None => write_frame(packet, dst),
+        };
+        if let Err(error) = result {
+            dst.truncate(start);
+            return Err(error);
+        }
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.apply(&mut dst[start..]);
+        }
+        Ok(())
+    }
+}
+
+/// Reads [`Frame`]s from and buffers outgoing packets for a connection.
+///
+/// Written packets are only sent once [`PacketStream::flush`] is called. Works for a whole stream
+/// as well as for either of its split halves, see [`PacketStream::into_split`].
+pub struct PacketStream<S> {
+    inner: S,
+    codec: PacketCodec,
+    read_buffer: BytesMut,
+    write_buffer: BytesMut,
+}
+impl<S> PacketStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            codec: PacketCodec::new(),
+            read_buffer: BytesMut::new(),
+            write_buffer: BytesMut::new(),
+        }
+    }
+    pub fn enable_compression(&mut self, threshold: usize) {
+        self.codec.enable_compression(threshold);
+    }
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> io::Result<()> {
+        self.codec.enable_encryption(shared_secret)
+    }
+    pub fn codec(&self) -> &PacketCodec {
+        &self.codec
+    }
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+impl<S: AsyncRead + AsyncWrite> PacketStream<S> {
+    /// Splits into a half that reads frames and a half that writes packets, e.g. to read and write
+    /// from different tasks. Compression, encryption and buffered data carry over to both halves.
+    pub fn into_split(self) -> (PacketStream<ReadHalf<S>>, PacketStream<WriteHalf<S>>) {
+        let (reader, writer) = tokio::io::split(self.inner);
+        let (decoder, encoder) = self.codec.split();
+        let reader = PacketStream {
+            inner: reader,
+            codec: decoder,
+            read_buffer: self.read_buffer,
+            write_buffer: BytesMut::new(),
+        };
+        let writer = PacketStream {
+            inner: writer,
+            codec: encoder,
+            read_buffer: BytesMut::new(),
+            write_buffer: self.write_buffer,
+        };
+        (reader, writer)
+    }
+}
+impl<S: AsyncRead + Unpin> PacketStream<S> {
+    /// Returns `None` once the other side closed the connection between two frames.
+    pub async fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        loop {
+            if let Some(frame) = self.codec.decode(&mut self.read_buffer)? {
+                return Ok(Some(frame));
+            }
+            if self.inner.read_buf(&mut self.read_buffer).await? == 0 {
+                if self.read_buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Connection closed in the middle of a frame!",
+                ));
+            }
+        }
+    }
+}
+impl<S: AsyncWrite + Unpin> PacketStream<S> {
+    pub fn write_packet(&mut self, packet: &[u8]) -> io::Result<()> {
+        self.codec.encode(packet, &mut self.write_buffer)
+    }
+    /// Like [`PacketStream::write_packet`], but encodes the packet straight into the write buffer.
+    pub fn write_packet_with_id<P: MinecraftPacket>(
+        &mut self,
+        packet: &P,
+        id: i32,
+    ) -> io::Result<()> {
+        self.codec.encode_packet(packet, id, &mut self.write_buffer)
+    }
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.write_buffer).await?;
+        self.write_buffer.clear();
+        self.inner.flush().await
+    }
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        self.flush().await?;
+        self.inner.shutdown().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::duplex;
+
+    use super::*;
+    use crate::{
+        codec::Json,
+        error::ProtocolError,
+        packet::{
+            ConnectionState, PacketDirection,
+            status::clientbound::{PongResponsePacket, StatusResponsePacket},
+            types::{StatusResponse, StatusResponseVersion},
+        },
+    };
+
+    const SHARED_SECRET: [u8; 16] = [7; 16];
+
+    // Claims to be a single byte long, like a hand-written codec with a wrong `encoded_len`.
+    struct MisreportedPacket(Vec<u8>);
+    impl MinecraftPacket for MisreportedPacket {
+        const ID: i32 = 0x02;
+        const STATE: ConnectionState = ConnectionState::Play;
+        const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+        const NAME: &'static str = "Misreported";
+
+        fn encoded_len(&self) -> usize {
+            1
+        }
+        fn encode_with_id<W: io::Write>(&self, id: i32, writer: &mut W) -> io::Result<()> {
+            VarInt(id).encode(writer)?;
+            writer.write_all(&self.0)
+        }
+        fn deserialize(_buffer: &mut PacketBuffer) -> Result<Self, ProtocolError> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn partial_frame() {
+        let mut codec = PacketCodec::new();
+        let mut buffer = BytesMut::from(&[0x03, 0x01, 0xAA][..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        buffer.extend_from_slice(&[0xBB]);
+        let frame = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(frame.id, 1);
+        assert_eq!(&frame.body[..], &[0xAA, 0xBB]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn frame_too_big() {
+        let mut codec = PacketCodec::new();
+        let mut buffer = BytesMut::from(&[0xFF, 0xFF, 0xFF, 0x01][..]);
+        assert!(codec.decode(&mut buffer).is_err());
+
+        let packet = vec![0; MAX_FRAME_SIZE + 1];
+        assert!(codec.encode(&packet[..], &mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn encode_packet_in_place() {
+        let small = PongResponsePacket::new(42);
+        let large = StatusResponsePacket::new(Json(StatusResponse {
+            version: StatusResponseVersion {
+                name: "a".repeat(300),
+                protocol: VarInt(774),
+            },
+            players: None,
+            description: None,
+            favicon: None,
+            enforces_secure_chat: false,
+        }));
+        let serialized_large = large.serialize().unwrap();
+        assert_eq!(
+            serialized_large.len(),
+            1 + large.encoded_len(),
+            "encoded_len has to match the serialized packet"
+        );
+
+        for threshold in [None, Some(256)] {
+            let mut codec = PacketCodec::new();
+            let mut expected_codec = PacketCodec::new();
+            if let Some(threshold) = threshold {
+                codec.enable_compression(threshold);
+                expected_codec.enable_compression(threshold);
+            }
+            codec.enable_encryption(&SHARED_SECRET).unwrap();
+            expected_codec.enable_encryption(&SHARED_SECRET).unwrap();
+            let (mut buffer, mut expected) = (BytesMut::new(), BytesMut::new());
+            codec.encode_packet(&small, 0x01, &mut buffer).unwrap();
+            codec.encode_packet(&large, 0x00, &mut buffer).unwrap();
+            expected_codec
+                .encode(&small.serialize().unwrap()[..], &mut expected)
+                .unwrap();
+            expected_codec
+                .encode(&serialized_large[..], &mut expected)
+                .unwrap();
+            assert_eq!(buffer, expected);
+        }
+    }
+
+    #[test]
+    fn misreported_length() {
+        for threshold in [None, Some(256)] {
+            for size in [10, 200, 300] {
+                let packet = MisreportedPacket(vec![0x55; size]);
+                let mut codec = PacketCodec::new();
+                let mut expected_codec = PacketCodec::new();
+                if let Some(threshold) = threshold {
+                    codec.enable_compression(threshold);
+                    expected_codec.enable_compression(threshold);
+                }
+                let (mut buffer, mut expected) = (BytesMut::from(&[0xAA][..]), BytesMut::new());
+                codec.encode_packet(&packet, 0x02, &mut buffer).unwrap();
+                expected_codec
+                    .encode(&[&[0x02][..], &packet.0].concat()[..], &mut expected)
+                    .unwrap();
+                assert_eq!(buffer[0], 0xAA);
+                assert_eq!(buffer[1..], expected[..]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let (client, server) = duplex(64);
+        let mut client = PacketStream::new(client);
+        let mut server = PacketStream::new(server);
+        let large_packet = [vec![0x02], vec![0x55; 1000]].concat();
+
+        client.write_packet(&[0x00, 0x01]).unwrap();
+        client.flush().await.unwrap();
+        let frame = server.read_frame().await.unwrap().unwrap();
+        assert_eq!((frame.id, &frame.body[..]), (0, &[0x01][..]));
+
+        for stream in [&mut client, &mut server] {
+            stream.enable_compression(256);
+            stream.enable_encryption(&SHARED_SECRET).unwrap();
+        }
+        client.write_packet(&[0x01, 0x02, 0x03]).unwrap();
+        client.write_packet(&large_packet).unwrap();
+        let write = async {
+            client.flush().await.unwrap();
+            client
+        };
+        let read = async {
+            let small = server.read_frame().await.unwrap().unwrap();
+            let large = server.read_frame().await.unwrap().unwrap();
+            (small, large)
+        };
+        let (mut client, (small, large)) = tokio::join!(write, read);
+        assert_eq!((small.id, &small.body[..]), (1, &[0x02, 0x03][..]));
+        assert_eq!((large.id, &large.body[..]), (2, &large_packet[1..]));
+
+        client.shutdown().await.unwrap();
+        assert!(server.read_frame().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn split_halves() {
+        let (client, server) = duplex(64);
+        let (client_reader, client_writer) = tokio::io::split(client);
+        let mut client_reader = PacketStream::new(client_reader);
+        let mut client_writer = PacketStream::new(client_writer);
+        let mut server = PacketStream::new(server);
+
+        // The encrypted frame is already buffered by the server when the stream gets split.
+        client_writer.write_packet(&[0x00, 0x01]).unwrap();
+        client_writer.enable_compression(256);
+        client_writer.enable_encryption(&SHARED_SECRET).unwrap();
+        client_writer.write_packet(&[0x01, 0x02]).unwrap();
+        client_writer.flush().await.unwrap();
+        let frame = server.read_frame().await.unwrap().unwrap();
+        assert_eq!((frame.id, &frame.body[..]), (0, &[0x01][..]));
+
+        server.enable_compression(256);
+        server.enable_encryption(&SHARED_SECRET).unwrap();
+        let (mut server_reader, mut server_writer) = server.into_split();
+        assert!(server_reader.codec().is_encrypted() && server_writer.codec().is_encrypted());
+        let frame = server_reader.read_frame().await.unwrap().unwrap();
+        assert_eq!((frame.id, &frame.body[..]), (1, &[0x02][..]));
+
+        client_reader.enable_compression(256);
+        client_reader.enable_encryption(&SHARED_SECRET).unwrap();
+        let large_packet = [vec![0x02], vec![0x55; 1000]].concat();
+        let writer = tokio::spawn(async move {
+            client_writer.write_packet(&large_packet).unwrap();
+            client_writer.flush().await.unwrap();
+        });
+        let frame = server_reader.read_frame().await.unwrap().unwrap();
+        assert_eq!((frame.id, &frame.body[..]), (2, &[0x55; 1000][..]));
+        writer.await.unwrap();
+
+        server_writer.write_packet(&[0x03, 0x04]).unwrap();
+        server_writer.shutdown().await.unwrap();
+        let frame = client_reader.read_frame().await.unwrap().unwrap();
+        assert_eq!((frame.id, &frame.body[..]), (3, &[0x04][..]));
+    }
+}
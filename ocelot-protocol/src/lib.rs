@@ -1,4 +1,12 @@
 pub mod buffer;
 pub mod codec;
+pub mod compression;
+pub mod encryption;
+pub mod error;
+pub mod frame;
 pub mod packet;
 pub mod types;
+pub mod version;
+
+pub const MINECRAFT_VERSION: &str = "1.21.11";
+pub const PROTOCOL_VERSION: i32 = 774;
@@ -2,29 +2,68 @@ use ocelot_macros::MinecraftPacket;
 use ocelot_types::ResourceLocation;
 
 use crate::{
-    codec::PrefixedArray,
-    packet::types::{KnownPack, RegistryEntry},
+    codec::{NbtText, PrefixedArray},
+    packet::{
+        packet_enum,
+        types::{KnownPack, RegistryEntry},
+    },
 };
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:cookie_request",
+    state = Configuration,
+    direction = Clientbound
+)]
 pub struct CookieRequestPacket {
     key: ResourceLocation,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x03)]
+#[packet(
+    name = "minecraft:disconnect",
+    state = Configuration,
+    direction = Clientbound
+)]
+pub struct DisconnectPacket {
+    reason: NbtText,
+}
+
+#[derive(MinecraftPacket)]
+#[packet(
+    name = "minecraft:finish_configuration",
+    state = Configuration,
+    direction = Clientbound
+)]
 pub struct FinishConfigurationPacket {}
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x07)]
+#[packet(
+    name = "minecraft:registry_data",
+    state = Configuration,
+    direction = Clientbound
+)]
 pub struct RegistryDataPacket {
     registry_id: ResourceLocation,
     entries: PrefixedArray<RegistryEntry>,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x0E)]
+#[packet(
+    name = "minecraft:select_known_packs",
+    state = Configuration,
+    direction = Clientbound
+)]
 pub struct KnownPacksPacket {
     known_packs: PrefixedArray<KnownPack>,
 }
+
+packet_enum! {
+    pub enum ClientboundConfiguration(Configuration, Clientbound) {
+        CookieRequest(CookieRequestPacket),
+        Disconnect(DisconnectPacket),
+        FinishConfiguration(FinishConfigurationPacket),
+        RegistryData(RegistryDataPacket),
+        KnownPacks(KnownPacksPacket),
+    }
+}
@@ -2,12 +2,19 @@ use ocelot_macros::MinecraftPacket;
 use ocelot_types::{BoundedString, ResourceLocation};
 
 use crate::{
-    codec::PrefixedArray,
-    packet::types::{ChatMode, KnownPack, MainHand, ParticleStatus},
+    codec::{PrefixedArray, SharedRemainingBytes},
+    packet::{
+        packet_enum,
+        types::{ChatMode, KnownPack, MainHand, ParticleStatus},
+    },
 };
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:client_information",
+    state = Configuration,
+    direction = Serverbound
+)]
 pub struct ClientInformationPacket {
     locale: BoundedString<16>,
     view_distance: i8,
@@ -21,18 +28,39 @@ pub struct ClientInformationPacket {
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x02)]
+#[packet(
+    name = "minecraft:custom_payload",
+    state = Configuration,
+    direction = Serverbound
+)]
 pub struct PluginMessagePacket {
     channel: ResourceLocation,
-    data: Vec<u8>,
+    data: SharedRemainingBytes,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x03)]
+#[packet(
+    name = "minecraft:finish_configuration",
+    state = Configuration,
+    direction = Serverbound
+)]
 pub struct AcknowledgeFinishConfigurationPacket {}
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x07)]
+#[packet(
+    name = "minecraft:select_known_packs",
+    state = Configuration,
+    direction = Serverbound
+)]
 pub struct KnownPacksPacket {
     known_packs: PrefixedArray<KnownPack>,
 }
+
+packet_enum! {
+    pub enum ServerboundConfiguration(Configuration, Serverbound) {
+        ClientInformation(ClientInformationPacket),
+        PluginMessage(PluginMessagePacket),
+        AcknowledgeFinishConfiguration(AcknowledgeFinishConfigurationPacket),
+        KnownPacks(KnownPacksPacket),
+    }
+}
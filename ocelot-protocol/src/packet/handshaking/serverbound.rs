@@ -1,13 +1,23 @@
 use ocelot_macros::MinecraftPacket;
 use ocelot_types::{BoundedString, VarInt};
 
-use crate::packet::types::Intent;
+use crate::packet::{packet_enum, types::Intent};
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:intention",
+    state = Handshaking,
+    direction = Serverbound
+)]
 pub struct HandshakePacket {
     protocol_version: VarInt,
     server_address: BoundedString<255>,
     server_port: u16,
     intent: Intent,
 }
+
+packet_enum! {
+    pub enum ServerboundHandshaking(Handshaking, Serverbound) {
+        Handshake(HandshakePacket),
+    }
+}
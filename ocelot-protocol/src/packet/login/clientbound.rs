@@ -3,27 +3,39 @@ use ocelot_types::text::TextComponent;
 use ocelot_types::{BoundedString, ResourceLocation, VarInt};
 use uuid::Uuid;
 
-use crate::codec::{BoundedPrefixedArray, Json, PrefixedArray};
+use crate::codec::{BoundedPrefixedArray, Json, PrefixedBytes, RemainingBytes};
 
-use crate::packet::types::Properties;
+use crate::packet::{packet_enum, types::Properties};
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:login_disconnect",
+    state = Login,
+    direction = Clientbound
+)]
 pub struct DisconnectPacket {
     text_component: Json<TextComponent>,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x01)]
+#[packet(
+    name = "minecraft:hello",
+    state = Login,
+    direction = Clientbound
+)]
 pub struct EncryptionRequestPacket {
     server_id: BoundedString<20>,
-    public_key: PrefixedArray<u8>,
-    verify_token: PrefixedArray<u8>,
+    public_key: PrefixedBytes<512>,
+    verify_token: PrefixedBytes<256>,
     should_authenticate: bool,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x02)]
+#[packet(
+    name = "minecraft:login_finished",
+    state = Login,
+    direction = Clientbound
+)]
 pub struct LoginSuccessPacket {
     uuid: Uuid,
     username: BoundedString<16>,
@@ -31,21 +43,44 @@ pub struct LoginSuccessPacket {
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x03)]
+#[packet(
+    name = "minecraft:login_compression",
+    state = Login,
+    direction = Clientbound
+)]
 pub struct SetCompressionPacket {
     threshold: VarInt,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x04)]
+#[packet(
+    name = "minecraft:custom_query",
+    state = Login,
+    direction = Clientbound
+)]
 pub struct LoginPluginRequestPacket {
     message_id: VarInt,
     channel: ResourceLocation,
-    data: Vec<u8>,
+    data: RemainingBytes,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x05)]
+#[packet(
+    name = "minecraft:cookie_request",
+    state = Login,
+    direction = Clientbound
+)]
 pub struct CookieRequestPacket {
     key: ResourceLocation,
 }
+
+packet_enum! {
+    pub enum ClientboundLogin(Login, Clientbound) {
+        Disconnect(DisconnectPacket),
+        EncryptionRequest(EncryptionRequestPacket),
+        LoginSuccess(LoginSuccessPacket),
+        SetCompression(SetCompressionPacket),
+        LoginPluginRequest(LoginPluginRequestPacket),
+        CookieRequest(CookieRequestPacket),
+    }
+}
@@ -2,36 +2,69 @@ use ocelot_macros::MinecraftPacket;
 use ocelot_types::{BoundedString, ResourceLocation, VarInt};
 use uuid::Uuid;
 
-use crate::codec::{BoundedPrefixedArray, PrefixedArray};
+use crate::{
+    codec::{PrefixedBytes, SharedRemainingBytes},
+    packet::packet_enum,
+};
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:hello",
+    state = Login,
+    direction = Serverbound
+)]
 pub struct LoginStartPacket {
     name: BoundedString<16>,
     player_uuid: Uuid,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x01)]
+#[packet(
+    name = "minecraft:key",
+    state = Login,
+    direction = Serverbound
+)]
 pub struct EncryptionResponsePacket {
-    shared_secret: PrefixedArray<u8>,
-    verify_token: PrefixedArray<u8>,
+    shared_secret: PrefixedBytes<256>,
+    verify_token: PrefixedBytes<256>,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x02)]
+#[packet(
+    name = "minecraft:custom_query_answer",
+    state = Login,
+    direction = Serverbound
+)]
 pub struct LoginPluginResponsePacket {
     message_id: VarInt,
-    data: Option<Vec<u8>>,
+    data: Option<SharedRemainingBytes>,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x03)]
+#[packet(
+    name = "minecraft:login_acknowledged",
+    state = Login,
+    direction = Serverbound
+)]
 pub struct LoginAcknowledgedPacket {}
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x04)]
+#[packet(
+    name = "minecraft:cookie_response",
+    state = Login,
+    direction = Serverbound
+)]
 pub struct CookieResponsePacket {
     key: ResourceLocation,
-    payload: Option<BoundedPrefixedArray<i8, 5120>>,
+    payload: Option<PrefixedBytes<5120>>,
+}
+
+packet_enum! {
+    pub enum ServerboundLogin(Login, Serverbound) {
+        LoginStart(LoginStartPacket),
+        EncryptionResponse(EncryptionResponsePacket),
+        LoginPluginResponse(LoginPluginResponsePacket),
+        LoginAcknowledged(LoginAcknowledgedPacket),
+        CookieResponse(CookieResponsePacket),
+    }
 }
@@ -5,12 +5,296 @@ pub mod play;
 pub mod status;
 pub mod types;
 
-use crate::buffer::PacketBuffer;
+use crate::{
+    buffer::{PacketBuffer, PacketWriter},
+    error::ProtocolError,
+    version::ProtocolVersion,
+};
 
-use std::io;
+use ocelot_types::VarInt;
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Write},
+};
 
 pub trait MinecraftPacket: Sized {
+    /// ID in the latest protocol, other versions map it through their [`ProtocolVersion`].
+    const ID: i32;
+    const STATE: ConnectionState;
+    const DIRECTION: PacketDirection;
+    /// Name for logs and errors, e.g. `ServerboundConfiguration::ClientInformation`.
+    const NAME: &'static str;
+
+    fn get_id(&self) -> i32 {
+        Self::ID
+    }
+    /// Number of bytes the fields take, without the ID.
+    fn encoded_len(&self) -> usize;
+    fn encode_with_id<W: Write>(&self, id: i32, writer: &mut W) -> io::Result<()>;
+    fn serialize(&self) -> io::Result<Vec<u8>> {
+        self.serialize_with_id(Self::ID)
+    }
+    fn serialize_with_id(&self, id: i32) -> io::Result<Vec<u8>> {
+        let mut writer = PacketWriter::with_capacity(VarInt(id).encoded_len() + self.encoded_len());
+        self.encode_with_id(id, &mut writer)?;
+        Ok(writer.build())
+    }
+    fn serialize_for(&self, version: &ProtocolVersion) -> io::Result<Vec<u8>> {
+        self.serialize_with_id(Self::id_for(version)?)
+    }
+    /// ID of the packet in the given protocol version.
+    fn id_for(version: &ProtocolVersion) -> io::Result<i32> {
+        version
+            .packet_id(Self::STATE, Self::DIRECTION, Self::ID)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("Packet doesn't exist in {}!", version.name),
+                )
+            })
+    }
+    fn deserialize(buffer: &mut PacketBuffer) -> Result<Self, ProtocolError>;
+    /// Like [`MinecraftPacket::deserialize`], but the packet has to consume the whole buffer.
+    /// A lenient buffer accepts trailing bytes and leaves them unread for the caller to report.
+    fn deserialize_exact(buffer: &mut PacketBuffer) -> Result<Self, ProtocolError> {
+        let packet = Self::deserialize(buffer)?;
+        match buffer.remaining() {
+            0 => Ok(packet),
+            _ if buffer.is_lenient() => Ok(packet),
+            remaining => Err(ProtocolError::TrailingBytes(remaining).in_field(Self::NAME)),
+        }
+    }
+}
+
+/// All packets of one state and direction, see [`packet_enum`].
+pub trait PacketEnum: Sized {
+    const STATE: ConnectionState;
+    const DIRECTION: PacketDirection;
+
+    /// Returns `None` for IDs without a packet in this state.
+    fn decode(id: i32, buffer: &mut PacketBuffer) -> Result<Option<Self>, ProtocolError>;
+    /// Like [`PacketEnum::decode`], but with an ID of the given protocol version.
+    fn decode_for(
+        version: &ProtocolVersion,
+        id: i32,
+        buffer: &mut PacketBuffer,
+    ) -> Result<Option<Self>, ProtocolError> {
+        match version.latest_packet_id(Self::STATE, Self::DIRECTION, id) {
+            Some(id) => Self::decode(id, buffer),
+            None => Ok(None),
+        }
+    }
     fn get_id(&self) -> i32;
+    fn name(&self) -> &'static str;
     fn serialize(&self) -> io::Result<Vec<u8>>;
-    fn deserialize(buffer: &mut PacketBuffer) -> io::Result<Self>;
+}
+
+/// Implemented by [`packet_enum`] for every packet it lists.
+///
+/// The derive requires it, so a packet that's missing from the enum of its state and direction
+/// doesn't compile instead of never being decoded.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` isn't listed in the `packet_enum!` of its state and direction"
+)]
+pub trait RegisteredPacket: MinecraftPacket {
+    type Enum: PacketEnum;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectionState {
+    Handshaking,
+    Status,
+    Login,
+    Configuration,
+    Play,
+}
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Handshaking => "Handshaking",
+            Self::Status => "Status",
+            Self::Login => "Login",
+            Self::Configuration => "Configuration",
+            Self::Play => "Play",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PacketDirection {
+    Serverbound,
+    Clientbound,
+}
+
+/// Declares the enum of every packet sent in one state and direction.
+///
+/// Fails to compile if two packets share an ID, a packet was derived for another state or direction
+/// or a packet is listed twice.
+macro_rules! packet_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident($state:ident, $direction:ident) {
+            $( $variant:ident($packet:ty), )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[allow(clippy::large_enum_variant)]
+        pub enum $name {
+            $( $variant($packet), )*
+        }
+        impl $crate::packet::PacketEnum for $name {
+            const STATE: $crate::packet::ConnectionState =
+                $crate::packet::ConnectionState::$state;
+            const DIRECTION: $crate::packet::PacketDirection =
+                $crate::packet::PacketDirection::$direction;
+
+            fn decode(
+                id: i32,
+                buffer: &mut $crate::buffer::PacketBuffer,
+            ) -> Result<Option<Self>, $crate::error::ProtocolError> {
+                Ok(Some(match id {
+                    $( <$packet as $crate::packet::MinecraftPacket>::ID => Self::$variant(
+                        <$packet as $crate::packet::MinecraftPacket>::deserialize_exact(buffer)?,
+                    ), )*
+                    _ => return Ok(None),
+                }))
+            }
+            fn get_id(&self) -> i32 {
+                match self {
+                    $( Self::$variant(_) => <$packet as $crate::packet::MinecraftPacket>::ID, )*
+                }
+            }
+            fn name(&self) -> &'static str {
+                match self {
+                    $( Self::$variant(_) => stringify!($variant), )*
+                }
+            }
+            fn serialize(&self) -> std::io::Result<Vec<u8>> {
+                match self {
+                    $( Self::$variant(packet) => $crate::packet::MinecraftPacket::serialize(packet), )*
+                }
+            }
+        }
+        $(
+            impl From<$packet> for $name {
+                fn from(packet: $packet) -> Self {
+                    Self::$variant(packet)
+                }
+            }
+            impl $crate::packet::RegisteredPacket for $packet {
+                type Enum = $name;
+            }
+        )*
+        const _: () = {
+            let ids = [$( <$packet as $crate::packet::MinecraftPacket>::ID, )*];
+            let mut i = 0;
+            while i < ids.len() {
+                let mut j = i + 1;
+                while j < ids.len() {
+                    if ids[i] == ids[j] {
+                        panic!(concat!("Duplicate packet ID in ", stringify!($name), "!"));
+                    }
+                    j += 1;
+                }
+                i += 1;
+            }
+            $(
+                if <$packet as $crate::packet::MinecraftPacket>::STATE as u8 != $crate::packet::ConnectionState::$state as u8
+                    || <$packet as $crate::packet::MinecraftPacket>::DIRECTION as u8
+                        != $crate::packet::PacketDirection::$direction as u8
+                {
+                    panic!(concat!(
+                        stringify!($packet),
+                        " doesn't belong into ",
+                        stringify!($name),
+                        "!"
+                    ));
+                }
+            )*
+        };
+    };
+}
+pub(crate) use packet_enum;
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{
+        MinecraftPacket, PacketEnum,
+        configuration::serverbound::{ClientInformationPacket, PluginMessagePacket},
+        handshaking::serverbound::HandshakePacket,
+        status::serverbound::{PingRequestPacket, ServerboundStatus},
+    };
+    use crate::buffer::PacketBuffer;
+
+    #[test]
+    fn decode_packet_enum() {
+        let data = 42i64.to_be_bytes();
+        let packet =
+            ServerboundStatus::decode(PingRequestPacket::ID, &mut PacketBuffer::new(data.to_vec()))
+                .unwrap()
+                .unwrap();
+        let ServerboundStatus::PingRequest(ping) = &packet else {
+            panic!("Decoded the wrong packet!");
+        };
+        assert_eq!(*ping.get_timestamp(), 42);
+        assert_eq!(packet.get_id(), 0x01);
+        assert_eq!(packet.serialize().unwrap(), [&[0x01][..], &data].concat());
+
+        assert!(
+            ServerboundStatus::decode(0x7F, &mut PacketBuffer::new(Vec::new()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn error_context() {
+        let data = [&[17][..], &[b'a'; 17]].concat();
+        let error = ClientInformationPacket::deserialize(&mut PacketBuffer::new(data.to_vec()))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "ServerboundConfiguration::ClientInformation.locale: string too long (17 > 16)"
+        );
+
+        let data = [0x86, 0x06, 0x00, 0x63, 0xDD, 0x04];
+        let error = HandshakePacket::deserialize(&mut PacketBuffer::new(data.to_vec()))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "ServerboundHandshaking::Handshake.intent: invalid Intent discriminant (4)"
+        );
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let data = [&42i64.to_be_bytes()[..], &[0xAA, 0xBB]].concat();
+        let mut buffer = PacketBuffer::new(data.to_vec());
+        let error = ServerboundStatus::decode(PingRequestPacket::ID, &mut buffer)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "ServerboundStatus::PingRequest: 2 trailing bytes"
+        );
+        assert_eq!((buffer.position(), buffer.remaining()), (8, 2));
+
+        let mut buffer = PacketBuffer::new(data.to_vec()).lenient(true);
+        let packet = ServerboundStatus::decode(PingRequestPacket::ID, &mut buffer).unwrap();
+        assert!(matches!(packet, Some(ServerboundStatus::PingRequest(_))));
+        assert_eq!((buffer.position(), buffer.remaining()), (8, 2));
+
+        // The plugin message data consumes everything after the channel.
+        let data = Bytes::from([&[0x09][..], b"ocelot:hi", &[0x01, 0x02, 0x03]].concat());
+        let packet =
+            PluginMessagePacket::deserialize_exact(&mut PacketBuffer::new(data.clone())).unwrap();
+        assert_eq!(packet.get_data().0, &[0x01, 0x02, 0x03][..]);
+        // Sliced out of the packet instead of copied
+        assert_eq!(packet.get_data().0.as_ptr(), data[10..].as_ptr());
+    }
 }
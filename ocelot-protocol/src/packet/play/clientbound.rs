@@ -2,40 +2,40 @@ use ocelot_macros::MinecraftPacket;
 use ocelot_types::{ResourceLocation, VarInt};
 
 use crate::{
-    codec::{MinecraftCodec, PrefixedArray},
-    packet::types::{GameEvent, GameMode, TeleportFlags},
-    types::Position,
+    codec::{NbtText, PrefixedArray},
+    packet::{
+        packet_enum,
+        types::{DeathLocation, GameEvent, GameMode, TeleportFlags},
+    },
 };
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x26)]
+#[packet(
+    name = "minecraft:disconnect",
+    state = Play,
+    direction = Clientbound
+)]
+pub struct DisconnectPacket {
+    reason: NbtText,
+}
+
+#[derive(MinecraftPacket)]
+#[packet(
+    name = "minecraft:game_event",
+    state = Play,
+    direction = Clientbound
+)]
 pub struct GameEventPacket {
     event: GameEvent,
     value: f32,
 }
 
-// TODO: change this at some point
-pub struct DeathLocation {
-    dimension_name: ResourceLocation,
-    location: Position,
-}
-
-impl MinecraftCodec for DeathLocation {
-    fn encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        self.dimension_name.encode(writer)?;
-        self.location.encode(writer)
-    }
-
-    fn decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
-        Ok(Self {
-            dimension_name: ResourceLocation::decode(reader)?,
-            location: Position::decode(reader)?,
-        })
-    }
-}
-
 #[derive(MinecraftPacket)]
-#[packet(id = 0x30)]
+#[packet(
+    name = "minecraft:login",
+    state = Play,
+    direction = Clientbound
+)]
 pub struct LoginPacket {
     entity_id: i32,
     hardcore: bool,
@@ -61,7 +61,11 @@ pub struct LoginPacket {
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x46)]
+#[packet(
+    name = "minecraft:player_position",
+    state = Play,
+    direction = Clientbound
+)]
 pub struct SynchronizePlayerPositionPacket {
     teleport_id: VarInt,
     x: f64,
@@ -74,3 +78,12 @@ pub struct SynchronizePlayerPositionPacket {
     pitch: f32,
     flags: TeleportFlags,
 }
+
+packet_enum! {
+    pub enum ClientboundPlay(Play, Clientbound) {
+        Disconnect(DisconnectPacket),
+        GameEvent(GameEventPacket),
+        Login(LoginPacket),
+        SynchronizePlayerPosition(SynchronizePlayerPositionPacket),
+    }
+}
@@ -1,5 +1,17 @@
 use ocelot_macros::MinecraftPacket;
 
+use crate::packet::packet_enum;
+
 #[derive(MinecraftPacket)]
-#[packet(id = 0x0C)]
+#[packet(
+    name = "minecraft:client_tick_end",
+    state = Play,
+    direction = Serverbound
+)]
 pub struct ClientTickEndPacket {}
+
+packet_enum! {
+    pub enum ServerboundPlay(Play, Serverbound) {
+        ClientTickEnd(ClientTickEndPacket),
+    }
+}
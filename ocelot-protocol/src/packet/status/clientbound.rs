@@ -1,15 +1,33 @@
 use ocelot_macros::MinecraftPacket;
 
-use crate::{codec::Json, packet::types::StatusResponse};
+use crate::{
+    codec::Json,
+    packet::{packet_enum, types::StatusResponse},
+};
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:status_response",
+    state = Status,
+    direction = Clientbound
+)]
 pub struct StatusResponsePacket {
     response: Json<StatusResponse>,
 }
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x01)]
+#[packet(
+    name = "minecraft:pong_response",
+    state = Status,
+    direction = Clientbound
+)]
 pub struct PongResponsePacket {
     timestamp: i64,
 }
+
+packet_enum! {
+    pub enum ClientboundStatus(Status, Clientbound) {
+        StatusResponse(StatusResponsePacket),
+        PongResponse(PongResponsePacket),
+    }
+}
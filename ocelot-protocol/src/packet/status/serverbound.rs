@@ -1,11 +1,28 @@
 use ocelot_macros::MinecraftPacket;
 
+use crate::packet::packet_enum;
+
 #[derive(MinecraftPacket)]
-#[packet(id = 0x00)]
+#[packet(
+    name = "minecraft:status_request",
+    state = Status,
+    direction = Serverbound
+)]
 pub struct StatusRequestPacket {}
 
 #[derive(MinecraftPacket)]
-#[packet(id = 0x01)]
+#[packet(
+    name = "minecraft:ping_request",
+    state = Status,
+    direction = Serverbound
+)]
 pub struct PingRequestPacket {
     timestamp: i64,
 }
+
+packet_enum! {
+    pub enum ServerboundStatus(Status, Serverbound) {
+        StatusRequest(StatusRequestPacket),
+        PingRequest(PingRequestPacket),
+    }
+}
@@ -1,9 +1,11 @@
 use ocelot_macros::MinecraftCodec;
-use ocelot_types::{BoundedString, ResourceLocation, VarInt, text::TextComponent};
+use ocelot_types::{
+    BoundedString, ResourceLocation, VarInt, coordinates::BlockPos, text::TextComponent,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::bitfield;
+use crate::{bitfield, codec::Nbt};
 
 #[derive(MinecraftCodec)]
 #[codec(via = VarInt)]
@@ -20,7 +22,8 @@ pub struct StatusResponse {
     pub players: Option<StatusResponsePlayers>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<TextComponent>,
-    //pub favicon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
     #[serde(rename = "enforcesSecureChat")]
     pub enforces_secure_chat: bool,
 }
@@ -47,15 +50,15 @@ pub struct StatusResponsePlayer {
 
 #[derive(MinecraftCodec)]
 pub struct Properties {
-    name: BoundedString<64>,
-    value: BoundedString<32767>,
-    signature: Option<BoundedString<1024>>,
+    pub name: BoundedString<64>,
+    pub value: BoundedString<32767>,
+    pub signature: Option<BoundedString<1024>>,
 }
 
 #[derive(MinecraftCodec)]
 pub struct RegistryEntry {
     pub id: ResourceLocation,
-    pub data: Option<Vec<u8>>, // TODO: has to be nbt data
+    pub data: Option<Nbt>,
 }
 
 #[derive(MinecraftCodec)]
@@ -67,10 +70,13 @@ pub struct KnownPack {
 
 #[derive(MinecraftCodec)]
 #[codec(via = VarInt)]
+#[repr(i32)]
 pub enum ChatMode {
     Enabled = 0,
     CommandsOnly = 1,
     Hidden = 2,
+    #[codec(other)]
+    Unknown(i32),
 }
 
 #[derive(MinecraftCodec)]
@@ -82,10 +88,13 @@ pub enum MainHand {
 
 #[derive(MinecraftCodec)]
 #[codec(via = VarInt)]
+#[repr(i32)]
 pub enum ParticleStatus {
     All = 0,
     Decreased = 1,
     Minimal = 2,
+    #[codec(other)]
+    Unknown(i32),
 }
 
 #[derive(MinecraftCodec)]
@@ -117,6 +126,12 @@ pub enum GameMode {
     Spectator = 3,
 }
 
+#[derive(MinecraftCodec)]
+pub struct DeathLocation {
+    pub dimension_name: ResourceLocation,
+    pub location: BlockPos,
+}
+
 bitfield!(TeleportFlags, i32, {
     RelativeX => 0,
     RelativeY => 1,
@@ -1,27 +1,492 @@
-use crate::codec::MinecraftCodec;
+use std::io::{self, Read, Write};
 
-pub struct Position {
-    x: i32,
-    y: i16,
-    z: i32,
+use ocelot_types::{
+    ResourceLocation, VarInt,
+    coordinates::{BlockPos, Vec3d},
+};
+
+use crate::{
+    buffer::PacketBuffer,
+    codec::{DecodeBudget, MinecraftCodec},
+    error::ProtocolError,
+};
+
+// see https://minecraft.wiki/w/Java_Edition_protocol/Packets#Position for specs
+impl MinecraftCodec for BlockPos {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.pack().encode(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(Self::unpack(i64::decode(reader)?))
+    }
+}
+
+/// Rotation in steps of 1/256 of a full turn.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Angle(pub u8);
+impl Angle {
+    /// Wraps around, so -90° and 270° are the same angle.
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self((degrees * 256.0 / 360.0).floor() as i32 as u8)
+    }
+    /// Between 0° and 360°.
+    pub fn degrees(self) -> f32 {
+        self.0 as f32 * 360.0 / 256.0
+    }
+}
+impl MinecraftCodec for Angle {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)
+    }
+    fn encoded_len(&self) -> usize {
+        1
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        Ok(Self(u8::decode(reader)?))
+    }
+}
+
+/// Bits of any length packed into longs, e.g. the light masks of a chunk.
+///
+/// Trailing empty longs aren't sent, so sets with the same bits are equal.
+#[derive(Clone, Debug, Default)]
+pub struct BitSet(Vec<u64>);
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn from_words(words: Vec<u64>) -> Self {
+        Self(words)
+    }
+    /// The longs that are sent, without trailing empty ones.
+    pub fn words(&self) -> &[u64] {
+        let length = self
+            .0
+            .iter()
+            .rposition(|word| *word != 0)
+            .map_or(0, |i| i + 1);
+        &self.0[..length]
+    }
+    pub fn get(&self, index: usize) -> bool {
+        self.0
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+    pub fn set(&mut self, index: usize, value: bool) {
+        let word = index / 64;
+        if word >= self.0.len() {
+            if !value {
+                return;
+            }
+            self.0.resize(word + 1, 0);
+        }
+        if value {
+            self.0[word] |= 1 << (index % 64);
+        } else {
+            self.0[word] &= !(1 << (index % 64));
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.words().is_empty()
+    }
+    pub fn count_ones(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+    /// Indices of the set bits in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.0.len() * 64).filter(|index| self.get(*index))
+    }
+}
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.words() == other.words()
+    }
+}
+impl Eq for BitSet {}
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(indices: I) -> Self {
+        let mut bits = Self::new();
+        indices.into_iter().for_each(|index| bits.set(index, true));
+        bits
+    }
+}
+impl MinecraftCodec for BitSet {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let words = self.words();
+        VarInt(words.len() as i32).encode(writer)?;
+        words
+            .iter()
+            .try_for_each(|word| writer.write_all(&word.to_be_bytes()))
+    }
+    fn encoded_len(&self) -> usize {
+        let length = self.words().len();
+        VarInt(length as i32).encoded_len() + length * 8
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let length = VarInt::decode(reader)?.0;
+        if length < 0 {
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        let mut words = budget.vec_with_capacity(length as usize)?;
+        for _ in 0..length {
+            words.push(i64::decode(reader)? as u64);
+        }
+        Ok(Self(words))
+    }
+}
+
+/// Exactly `BITS` bits sent as `BITS / 8` bytes rounded up, e.g. the acknowledged chat messages.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FixedBitSet<const BITS: usize>(Vec<u8>);
+impl<const BITS: usize> FixedBitSet<BITS> {
+    const BYTES: usize = BITS.div_ceil(8);
+
+    pub fn new() -> Self {
+        Self(vec![0; Self::BYTES])
+    }
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+    /// Bits past `BITS` are always unset.
+    pub fn get(&self, index: usize) -> bool {
+        index < BITS && self.0[index / 8] & (1 << (index % 8)) != 0
+    }
+    /// Panics if `index` isn't below `BITS`.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(
+            index < BITS,
+            "Bit {} is out of range for {} bits!",
+            index,
+            BITS
+        );
+        if value {
+            self.0[index / 8] |= 1 << (index % 8);
+        } else {
+            self.0[index / 8] &= !(1 << (index % 8));
+        }
+    }
+    pub fn count_ones(&self) -> usize {
+        (0..BITS).filter(|index| self.get(*index)).count()
+    }
+    /// Indices of the set bits in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..BITS).filter(|index| self.get(*index))
+    }
 }
+impl<const BITS: usize> Default for FixedBitSet<BITS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<const BITS: usize> MinecraftCodec for FixedBitSet<BITS> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+    fn encoded_len(&self) -> usize {
+        Self::BYTES
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        budget.allocate(Self::BYTES)?;
+        let mut bytes = vec![0; Self::BYTES];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Registry entries given by a tag or by their IDs (`ID Set` in the protocol).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum IdSet {
+    /// Name of the tag, without the leading `#`.
+    Tag(ResourceLocation),
+    Ids(Vec<i32>),
+}
+impl MinecraftCodec for IdSet {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Tag(tag) => {
+                VarInt(0).encode(writer)?;
+                tag.encode(writer)
+            }
+            Self::Ids(ids) => {
+                VarInt(ids.len() as i32 + 1).encode(writer)?;
+                ids.iter().try_for_each(|id| VarInt(*id).encode(writer))
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            Self::Tag(tag) => 1 + tag.encoded_len(),
+            Self::Ids(ids) => {
+                VarInt(ids.len() as i32 + 1).encoded_len()
+                    + ids
+                        .iter()
+                        .map(|id| VarInt(*id).encoded_len())
+                        .sum::<usize>()
+            }
+        }
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        match VarInt::decode(reader)?.0 {
+            0 => Ok(Self::Tag(ResourceLocation::decode_with_budget(
+                reader, budget,
+            )?)),
+            length if length < 0 => Err(ProtocolError::NegativeLength(length)),
+            length => {
+                let mut ids = budget.vec_with_capacity(length as usize - 1)?;
+                for i in 0..length - 1 {
+                    ids.push(
+                        VarInt::decode(reader)
+                            .map_err(|error| error.in_field(&format!("[{}]", i)))?
+                            .0,
+                    );
+                }
+                Ok(Self::Ids(ids))
+            }
+        }
+    }
+}
+
+/// A registry entry given by its ID or inline (`ID or X` in the protocol).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum IdOr<T> {
+    Id(i32),
+    Inline(T),
+}
+impl<T: MinecraftCodec> IdOr<T> {
+    fn from_id(id: i32) -> Result<Self, ProtocolError> {
+        match id {
+            id if id < 0 => Err(ProtocolError::Invalid(format!(
+                "invalid registry ID ({})",
+                id
+            ))),
+            id => Ok(Self::Id(id - 1)),
+        }
+    }
+}
+impl<T: MinecraftCodec> MinecraftCodec for IdOr<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Id(id) => VarInt(id + 1).encode(writer),
+            Self::Inline(value) => {
+                VarInt(0).encode(writer)?;
+                value.encode(writer)
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            Self::Id(id) => VarInt(id + 1).encoded_len(),
+            Self::Inline(value) => 1 + value.encoded_len(),
+        }
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        match VarInt::decode(reader)?.0 {
+            0 => Ok(Self::Inline(T::decode_with_budget(reader, budget)?)),
+            id => Self::from_id(id),
+        }
+    }
+    fn decode_shared(
+        buffer: &mut PacketBuffer,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        match VarInt::decode(buffer)?.0 {
+            0 => Ok(Self::Inline(T::decode_shared(buffer, budget)?)),
+            id => Self::from_id(id),
+        }
+    }
+}
+
+/// Low precision vector used for entity velocities, 15 bits per axis scaled by a shared factor.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct LpVec3(pub Vec3d);
+impl LpVec3 {
+    const MAX_QUANTIZED: f64 = 32766.0;
+    const ABS_MAX: f64 = 1.7179869183e10;
+    const ABS_MIN: f64 = 3.051944088384301e-5;
+    const CONTINUATION: i64 = 4;
+
+    fn sanitize(value: f64) -> f64 {
+        if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(-Self::ABS_MAX, Self::ABS_MAX)
+        }
+    }
+    fn pack(value: f64) -> i64 {
+        ((value * 0.5 + 0.5) * Self::MAX_QUANTIZED).round() as i64
+    }
+    fn unpack(value: i64) -> f64 {
+        ((value & 0x7FFF) as f64).min(Self::MAX_QUANTIZED) * 2.0 / Self::MAX_QUANTIZED - 1.0
+    }
+    /// The 48 packed bits and the scale bits that don't fit into them, `None` for zero vectors.
+    fn quantize(&self) -> Option<(i64, Option<VarInt>)> {
+        let (x, y, z) = (
+            Self::sanitize(self.0.x),
+            Self::sanitize(self.0.y),
+            Self::sanitize(self.0.z),
+        );
+        let max = x.abs().max(y.abs()).max(z.abs());
+        if max < Self::ABS_MIN {
+            return None;
+        }
+        let scale = max.ceil() as i64;
+        let continuation = scale & 3 != scale;
+        let scale_bits = if continuation {
+            scale & 3 | Self::CONTINUATION
+        } else {
+            scale
+        };
+        let packed = scale_bits
+            | Self::pack(x / scale as f64) << 3
+            | Self::pack(y / scale as f64) << 18
+            | Self::pack(z / scale as f64) << 33;
+        Some((packed, continuation.then_some(VarInt((scale >> 2) as i32))))
+    }
+}
+impl MinecraftCodec for LpVec3 {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let Some((packed, continuation)) = self.quantize() else {
+            return 0u8.encode(writer);
+        };
+        writer.write_all(&packed.to_le_bytes()[..2])?;
+        ((packed >> 16) as i32).encode(writer)?;
+        match continuation {
+            Some(scale) => scale.encode(writer),
+            None => Ok(()),
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self.quantize() {
+            Some((_, continuation)) => 6 + continuation.map_or(0, |scale| scale.encoded_len()),
+            None => 1,
+        }
+    }
+    fn decode_with_budget<R: Read>(
+        reader: &mut R,
+        _budget: &mut DecodeBudget,
+    ) -> Result<Self, ProtocolError> {
+        let low = u8::decode(reader)? as i64;
+        if low == 0 {
+            return Ok(Self(Vec3d::ZERO));
+        }
+        let middle = u8::decode(reader)? as i64;
+        let high = i32::decode(reader)? as u32 as i64;
+        let packed = high << 16 | middle << 8 | low;
+        let mut scale = low & 3;
+        if low & Self::CONTINUATION != 0 {
+            scale |= (VarInt::decode(reader)?.0 as u32 as i64) << 2;
+        }
+        let scale = scale as f64;
+        Ok(Self(Vec3d::new(
+            Self::unpack(packed >> 3) * scale,
+            Self::unpack(packed >> 18) * scale,
+            Self::unpack(packed >> 33) * scale,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: MinecraftCodec + PartialEq + std::fmt::Debug>(value: T, data: &[u8]) {
+        let mut encoded = Vec::new();
+        value.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, data);
+        assert_eq!(value.encoded_len(), data.len());
+        let mut reader = data;
+        assert_eq!(T::decode(&mut reader).unwrap(), value);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn position() {
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xC0];
+        round_trip(BlockPos::new(-1, -64, -1), &data);
+    }
+
+    #[test]
+    fn angle() {
+        round_trip(Angle(0x40), &[0x40]);
+        assert_eq!(Angle::from_degrees(90.0), Angle(64));
+        assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+        assert_eq!(Angle(192).degrees(), 270.0);
+    }
+
+    #[test]
+    fn bit_sets() {
+        let bits: BitSet = [0, 65].into_iter().collect();
+        assert!(bits.get(65) && !bits.get(64) && !bits.get(1000));
+        round_trip(
+            bits.clone(),
+            &[2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2],
+        );
+        // Trailing empty longs are dropped
+        let mut cleared = bits.clone();
+        cleared.set(65, false);
+        round_trip(cleared, &[1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(bits.iter_ones().collect::<Vec<_>>(), [0, 65]);
+        assert!(BitSet::decode(&mut &[0x80, 0x80, 0x80, 0x80, 0x01][..]).is_err());
+
+        let mut acknowledged = FixedBitSet::<20>::new();
+        for index in [0, 9, 19] {
+            acknowledged.set(index, true);
+        }
+        round_trip(acknowledged.clone(), &[0x01, 0x02, 0x08]);
+        assert_eq!(acknowledged.count_ones(), 3);
+        assert!(!acknowledged.get(20));
+    }
 
-impl MinecraftCodec for Position {
-    // see https://minecraft.wiki/w/Java_Edition_protocol/Packets#Position for specs
+    #[test]
+    fn id_sets() {
+        let tag = ResourceLocation::from("minecraft", "logs").unwrap();
+        round_trip(IdSet::Tag(tag), &[&[0, 14][..], b"minecraft:logs"].concat());
+        round_trip(IdSet::Ids(vec![1, 300]), &[3, 1, 0xAC, 0x02]);
+        round_trip(IdSet::Ids(Vec::new()), &[1]);
 
-    fn encode<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        let val = ((self.x as i64 & 0x3FFFFFF) << 38)
-            | ((self.z as i64 & 0x3FFFFFF) << 12)
-            | (self.y as i64 & 0xFFF);
-        val.encode(writer)
+        round_trip(IdOr::<i8>::Id(4), &[5]);
+        round_trip(IdOr::Inline(-1i8), &[0, 0xFF]);
     }
 
-    fn decode<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
-        let val = i64::decode(reader)?;
-        Ok(Self {
-            x: (val >> 38) as i32,
-            y: (val << 52 >> 52) as i16,
-            z: (val << 26 >> 38) as i32,
-        })
+    #[test]
+    fn low_precision_vectors() {
+        round_trip(LpVec3(Vec3d::ZERO), &[0]);
+        round_trip(
+            LpVec3(Vec3d::new(1.0, 0.0, -1.0)),
+            &[0xF1, 0xFF, 0x00, 0x00, 0xFF, 0xFF],
+        );
+        // Scales above 3 continue in a VarInt
+        round_trip(
+            LpVec3(Vec3d::new(5.0, 0.0, 0.0)),
+            &[0xF5, 0xFF, 0x7F, 0xFE, 0xFF, 0xFF, 0x01],
+        );
+        let LpVec3(velocity) =
+            LpVec3::decode(&mut &[0xF1, 0xAB, 0x12, 0x34, 0xFF, 0xFF][..]).unwrap();
+        let mut encoded = Vec::new();
+        LpVec3(velocity).encode(&mut encoded).unwrap();
+        assert_eq!(LpVec3::decode(&mut &encoded[..]).unwrap().0, velocity);
     }
 }
@@ -0,0 +1,174 @@
+use crate::{
+    MINECRAFT_VERSION, PROTOCOL_VERSION,
+    packet::{ConnectionState, PacketDirection},
+};
+
+/// A packet whose ID in an older protocol differs from the one of the latest protocol.
+pub struct PacketIdMapping {
+    pub state: ConnectionState,
+    pub direction: PacketDirection,
+    /// ID in the latest protocol, as listed in `packets.json`.
+    pub id: i32,
+    /// ID in this protocol or `None` if the packet doesn't exist in it.
+    pub protocol_id: Option<i32>,
+}
+
+pub struct ProtocolVersion {
+    pub protocol: i32,
+    pub name: &'static str,
+    /// Only packets whose ID changed since this version, every other packet keeps its ID.
+    pub packet_ids: &'static [PacketIdMapping],
+}
+impl ProtocolVersion {
+    /// Maps the ID of a packet in the latest protocol to its ID in this one.
+    pub fn packet_id(
+        &self,
+        state: ConnectionState,
+        direction: PacketDirection,
+        id: i32,
+    ) -> Option<i32> {
+        match self
+            .mappings(state, direction)
+            .find(|mapping| mapping.id == id)
+        {
+            Some(mapping) => mapping.protocol_id,
+            None => Some(id),
+        }
+    }
+    /// Maps an ID of this protocol to the ID the same packet has in the latest protocol.
+    pub fn latest_packet_id(
+        &self,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol_id: i32,
+    ) -> Option<i32> {
+        if let Some(mapping) = self
+            .mappings(state, direction)
+            .find(|mapping| mapping.protocol_id == Some(protocol_id))
+        {
+            return Some(mapping.id);
+        }
+        // The ID belongs to a packet that moved or doesn't exist in this protocol.
+        if self
+            .mappings(state, direction)
+            .any(|mapping| mapping.id == protocol_id)
+        {
+            return None;
+        }
+        Some(protocol_id)
+    }
+    fn mappings(
+        &self,
+        state: ConnectionState,
+        direction: PacketDirection,
+    ) -> impl Iterator<Item = &PacketIdMapping> {
+        self.packet_ids
+            .iter()
+            .filter(move |mapping| mapping.state == state && mapping.direction == direction)
+    }
+}
+
+pub const LATEST: ProtocolVersion = ProtocolVersion {
+    protocol: PROTOCOL_VERSION,
+    name: MINECRAFT_VERSION,
+    packet_ids: &[],
+};
+
+/// Ordered from the oldest to the latest protocol.
+pub const SUPPORTED_VERSIONS: &[ProtocolVersion] = &[LATEST];
+
+pub fn find(protocol: i32) -> Option<&'static ProtocolVersion> {
+    SUPPORTED_VERSIONS
+        .iter()
+        .find(|version| version.protocol == protocol)
+}
+
+pub fn oldest() -> &'static ProtocolVersion {
+    &SUPPORTED_VERSIONS[0]
+}
+
+pub fn latest() -> &'static ProtocolVersion {
+    &SUPPORTED_VERSIONS[SUPPORTED_VERSIONS.len() - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use ocelot_types::text::TextComponent;
+
+    use super::*;
+    use crate::{
+        buffer::PacketBuffer,
+        codec::NbtText,
+        packet::{
+            MinecraftPacket, PacketEnum,
+            play::clientbound::DisconnectPacket,
+            status::serverbound::{PingRequestPacket, ServerboundStatus, StatusRequestPacket},
+        },
+    };
+
+    // Swaps both status requests and drops the play disconnect.
+    const OLD: ProtocolVersion = ProtocolVersion {
+        protocol: 1,
+        name: "old",
+        packet_ids: &[
+            PacketIdMapping {
+                state: ConnectionState::Status,
+                direction: PacketDirection::Serverbound,
+                id: 0x00,
+                protocol_id: Some(0x01),
+            },
+            PacketIdMapping {
+                state: ConnectionState::Status,
+                direction: PacketDirection::Serverbound,
+                id: 0x01,
+                protocol_id: Some(0x00),
+            },
+            PacketIdMapping {
+                state: ConnectionState::Play,
+                direction: PacketDirection::Clientbound,
+                id: 0x20,
+                protocol_id: None,
+            },
+        ],
+    };
+
+    #[test]
+    fn packet_ids() {
+        let serverbound_status = (ConnectionState::Status, PacketDirection::Serverbound);
+        let clientbound_play = (ConnectionState::Play, PacketDirection::Clientbound);
+        let (state, direction) = serverbound_status;
+        assert_eq!(OLD.packet_id(state, direction, 0x00), Some(0x01));
+        assert_eq!(OLD.latest_packet_id(state, direction, 0x00), Some(0x01));
+        let (state, direction) = clientbound_play;
+        assert_eq!(OLD.packet_id(state, direction, 0x20), None);
+        assert_eq!(OLD.latest_packet_id(state, direction, 0x20), None);
+        assert_eq!(OLD.packet_id(state, direction, 0x26), Some(0x26));
+        assert_eq!(LATEST.packet_id(state, direction, 0x20), Some(0x20));
+    }
+
+    #[test]
+    fn versioned_packets() {
+        let data = 42i64.to_be_bytes();
+        let packet =
+            ServerboundStatus::decode_for(&OLD, 0x00, &mut PacketBuffer::new(data.to_vec()))
+                .unwrap()
+                .unwrap();
+        assert!(matches!(packet, ServerboundStatus::PingRequest(_)));
+
+        let serialized = StatusRequestPacket::new().serialize_for(&OLD).unwrap();
+        assert_eq!(serialized, [0x01]);
+        let serialized = PingRequestPacket::new(42).serialize_for(&LATEST).unwrap();
+        assert_eq!(serialized, [&[0x01][..], &data].concat());
+
+        let disconnect = DisconnectPacket::new(NbtText(TextComponent::text("Bye!")));
+        assert!(disconnect.serialize_for(&OLD).is_err());
+    }
+
+    #[test]
+    fn supported_versions() {
+        assert!(find(PROTOCOL_VERSION).is_some());
+        assert!(find(PROTOCOL_VERSION + 1).is_none());
+        assert_eq!(latest().protocol, PROTOCOL_VERSION);
+        assert!(oldest().protocol <= latest().protocol);
+    }
+}
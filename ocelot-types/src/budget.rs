@@ -0,0 +1,79 @@
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("decode budget exceeded ({requested} bytes requested, {remaining} left)")]
+pub struct BudgetExceeded {
+    pub requested: usize,
+    pub remaining: usize,
+}
+impl From<BudgetExceeded> for io::Error {
+    fn from(error: BudgetExceeded) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Limits how much memory decoding a single value may allocate.
+///
+/// Length prefixes are charged before anything is allocated for them, so a few bytes claiming a
+/// huge array or string fail instead of reserving the memory.
+#[derive(Clone, Debug)]
+pub struct DecodeBudget {
+    remaining: usize,
+}
+impl DecodeBudget {
+    /// Generous for the largest decompressed packet, including the overhead of decoded values.
+    pub const DEFAULT_LIMIT: usize = 32 * 1024 * 1024;
+    /// Vectors never reserve more than this up front and grow while their items are read.
+    pub const MAX_PREALLOCATION: usize = 64 * 1024;
+
+    pub fn new(limit: usize) -> Self {
+        Self { remaining: limit }
+    }
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), BudgetExceeded> {
+        if bytes > self.remaining {
+            return Err(BudgetExceeded {
+                requested: bytes,
+                remaining: self.remaining,
+            });
+        }
+        self.remaining -= bytes;
+        Ok(())
+    }
+    /// Charges `length` items of `T` and returns a vector whose capacity is capped for them.
+    pub fn vec_with_capacity<T>(&mut self, length: usize) -> Result<Vec<T>, BudgetExceeded> {
+        self.allocate(length.saturating_mul(size_of::<T>()))?;
+        let max_items = Self::MAX_PREALLOCATION / size_of::<T>().max(1);
+        Ok(Vec::with_capacity(length.min(max_items)))
+    }
+}
+impl Default for DecodeBudget {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        let mut budget = DecodeBudget::new(100);
+        budget.allocate(60).unwrap();
+        assert!(budget.allocate(41).is_err());
+        assert_eq!(budget.remaining(), 40);
+
+        let vec: Vec<u32> = budget.vec_with_capacity(10).unwrap();
+        assert!(vec.capacity() >= 10);
+        assert!(budget.vec_with_capacity::<u32>(1).is_err());
+        assert!(budget.vec_with_capacity::<u64>(usize::MAX).is_err());
+
+        let vec: Vec<u8> = DecodeBudget::default().vec_with_capacity(1 << 20).unwrap();
+        assert!(vec.capacity() < 1 << 20);
+    }
+}
@@ -0,0 +1,487 @@
+use std::{
+    cmp::Ordering,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+impl Direction {
+    /// Ordered by their protocol IDs.
+    pub const ALL: [Self; 6] = [
+        Self::Down,
+        Self::Up,
+        Self::North,
+        Self::South,
+        Self::West,
+        Self::East,
+    ];
+
+    pub const fn offset(self) -> (i32, i32, i32) {
+        match self {
+            Self::Down => (0, -1, 0),
+            Self::Up => (0, 1, 0),
+            Self::North => (0, 0, -1),
+            Self::South => (0, 0, 1),
+            Self::West => (-1, 0, 0),
+            Self::East => (1, 0, 0),
+        }
+    }
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::Down => Self::Up,
+            Self::Up => Self::Down,
+            Self::North => Self::South,
+            Self::South => Self::North,
+            Self::West => Self::East,
+            Self::East => Self::West,
+        }
+    }
+}
+
+/// Position of a block, sent as a packed long (`Position` in the protocol).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+impl BlockPos {
+    pub const ORIGIN: Self = Self::new(0, 0, 0);
+
+    const XZ_BITS: u32 = 26;
+    const Y_BITS: u32 = 12;
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+    /// The block containing the point.
+    pub fn containing(point: Vec3d) -> Self {
+        Self::new(
+            point.x.floor() as i32,
+            point.y.floor() as i32,
+            point.z.floor() as i32,
+        )
+    }
+    /// Packs the position into 26 bits of X, 26 of Z and 12 of Y, coordinates out of range wrap.
+    pub const fn pack(self) -> i64 {
+        pack_bits(self.x, Self::XZ_BITS, Self::XZ_BITS + Self::Y_BITS)
+            | pack_bits(self.z, Self::XZ_BITS, Self::Y_BITS)
+            | pack_bits(self.y, Self::Y_BITS, 0)
+    }
+    pub const fn unpack(value: i64) -> Self {
+        Self::new(
+            unpack_bits(value, Self::XZ_BITS, Self::XZ_BITS + Self::Y_BITS),
+            unpack_bits(value, Self::Y_BITS, 0),
+            unpack_bits(value, Self::XZ_BITS, Self::Y_BITS),
+        )
+    }
+    pub const fn offset(self, x: i32, y: i32, z: i32) -> Self {
+        Self::new(self.x + x, self.y + y, self.z + z)
+    }
+    pub const fn relative(self, direction: Direction, distance: i32) -> Self {
+        let (x, y, z) = direction.offset();
+        self.offset(x * distance, y * distance, z * distance)
+    }
+    pub const fn neighbor(self, direction: Direction) -> Self {
+        self.relative(direction, 1)
+    }
+    /// Neighbors in the order of [`Direction::ALL`].
+    pub fn neighbors(self) -> [Self; 6] {
+        Direction::ALL.map(|direction| self.neighbor(direction))
+    }
+    pub const fn chunk(self) -> ChunkPos {
+        ChunkPos::new(self.x >> 4, self.z >> 4)
+    }
+    pub const fn section(self) -> SectionPos {
+        SectionPos::new(self.x >> 4, self.y >> 4, self.z >> 4)
+    }
+    /// Coordinates inside the section, each in `0..16`.
+    pub const fn section_relative(self) -> (u8, u8, u8) {
+        (
+            (self.x & 15) as u8,
+            (self.y & 15) as u8,
+            (self.z & 15) as u8,
+        )
+    }
+    /// Lowest corner of the block.
+    pub fn corner(self) -> Vec3d {
+        Vec3d::new(self.x as f64, self.y as f64, self.z as f64)
+    }
+    pub fn center(self) -> Vec3d {
+        self.corner() + Vec3d::new(0.5, 0.5, 0.5)
+    }
+    pub const fn distance_squared(self, other: Self) -> i64 {
+        let x = (self.x - other.x) as i64;
+        let y = (self.y - other.y) as i64;
+        let z = (self.z - other.z) as i64;
+        x * x + y * y + z * z
+    }
+}
+/// Orders by Y, then Z, then X like vanilla, which is also the order blocks are stored in.
+impl Ord for BlockPos {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.z, self.x).cmp(&(other.y, other.z, other.x))
+    }
+}
+impl PartialOrd for BlockPos {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Add for BlockPos {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.offset(other.x, other.y, other.z)
+    }
+}
+impl Sub for BlockPos {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.offset(-other.x, -other.y, -other.z)
+    }
+}
+impl From<BlockPos> for ChunkPos {
+    fn from(position: BlockPos) -> Self {
+        position.chunk()
+    }
+}
+impl From<BlockPos> for SectionPos {
+    fn from(position: BlockPos) -> Self {
+        position.section()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+    /// Packs X into the low and Z into the high 32 bits, the key vanilla uses for chunk maps.
+    pub const fn pack(self) -> i64 {
+        (self.x as u32 as i64) | ((self.z as u32 as i64) << 32)
+    }
+    pub const fn unpack(value: i64) -> Self {
+        Self::new(value as i32, (value >> 32) as i32)
+    }
+    pub const fn offset(self, x: i32, z: i32) -> Self {
+        Self::new(self.x + x, self.z + z)
+    }
+    pub const fn section(self, y: i32) -> SectionPos {
+        SectionPos::new(self.x, y, self.z)
+    }
+    /// Block with the lowest X and Z in the chunk at the given height.
+    pub const fn min_block(self, y: i32) -> BlockPos {
+        BlockPos::new(self.x << 4, y, self.z << 4)
+    }
+    /// Chebyshev distance, which is what the view distance is measured in.
+    pub const fn distance(self, other: Self) -> u32 {
+        let x = self.x.abs_diff(other.x);
+        let z = self.z.abs_diff(other.z);
+        if x > z { x } else { z }
+    }
+}
+
+/// A 16×16×16 section of a chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct SectionPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+impl SectionPos {
+    const XZ_BITS: u32 = 22;
+    const Y_BITS: u32 = 20;
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+    /// Packs the position into 22 bits of X, 22 of Z and 20 of Y, as sent in section updates.
+    pub const fn pack(self) -> i64 {
+        pack_bits(self.x, Self::XZ_BITS, Self::XZ_BITS + Self::Y_BITS)
+            | pack_bits(self.z, Self::XZ_BITS, Self::Y_BITS)
+            | pack_bits(self.y, Self::Y_BITS, 0)
+    }
+    pub const fn unpack(value: i64) -> Self {
+        Self::new(
+            unpack_bits(value, Self::XZ_BITS, Self::XZ_BITS + Self::Y_BITS),
+            unpack_bits(value, Self::Y_BITS, 0),
+            unpack_bits(value, Self::XZ_BITS, Self::Y_BITS),
+        )
+    }
+    pub const fn offset(self, x: i32, y: i32, z: i32) -> Self {
+        Self::new(self.x + x, self.y + y, self.z + z)
+    }
+    pub const fn chunk(self) -> ChunkPos {
+        ChunkPos::new(self.x, self.z)
+    }
+    pub const fn min_block(self) -> BlockPos {
+        BlockPos::new(self.x << 4, self.y << 4, self.z << 4)
+    }
+}
+/// Orders by Y, then Z, then X, the same as [`BlockPos`].
+impl Ord for SectionPos {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.z, self.x).cmp(&(other.y, other.z, other.x))
+    }
+}
+impl PartialOrd for SectionPos {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Vec3d {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+impl Vec3d {
+    pub const ZERO: Self = Self::new(0.0, 0.0, 0.0);
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+    pub fn offset(self, x: f64, y: f64, z: f64) -> Self {
+        Self::new(self.x + x, self.y + y, self.z + z)
+    }
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+    pub fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+    pub fn distance_squared(self, other: Self) -> f64 {
+        (self - other).length_squared()
+    }
+    /// Scaled to a length of 1, or zero if it is (almost) zero.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length < 1.0e-5 {
+            return Self::ZERO;
+        }
+        self * (1.0 / length)
+    }
+    pub fn block_pos(self) -> BlockPos {
+        BlockPos::containing(self)
+    }
+}
+impl Add for Vec3d {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.offset(other.x, other.y, other.z)
+    }
+}
+impl Sub for Vec3d {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.offset(-other.x, -other.y, -other.z)
+    }
+}
+impl Mul<f64> for Vec3d {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self {
+        Self::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+impl Neg for Vec3d {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self * -1.0
+    }
+}
+impl From<BlockPos> for Vec3d {
+    fn from(position: BlockPos) -> Self {
+        position.corner()
+    }
+}
+
+/// Axis-aligned bounding box, e.g. of an entity or a block shape.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Aabb {
+    pub min: Vec3d,
+    pub max: Vec3d,
+}
+impl Aabb {
+    /// The box spanned by both corners, in any order.
+    pub fn new(a: Vec3d, b: Vec3d) -> Self {
+        Self {
+            min: Vec3d::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Vec3d::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+    pub fn of_block(position: BlockPos) -> Self {
+        let min = position.corner();
+        Self::new(min, min.offset(1.0, 1.0, 1.0))
+    }
+    /// Box of an entity standing at `position`.
+    pub fn of_entity(position: Vec3d, width: f64, height: f64) -> Self {
+        let half = width / 2.0;
+        Self::new(
+            position.offset(-half, 0.0, -half),
+            position.offset(half, height, half),
+        )
+    }
+    pub fn size(&self) -> Vec3d {
+        self.max - self.min
+    }
+    pub fn center(&self) -> Vec3d {
+        (self.min + self.max) * 0.5
+    }
+    pub fn offset(&self, offset: Vec3d) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+    /// Grows the box by `amount` in every direction, a negative amount shrinks it.
+    pub fn inflate(&self, amount: f64) -> Self {
+        Self::new(
+            self.min.offset(-amount, -amount, -amount),
+            self.max.offset(amount, amount, amount),
+        )
+    }
+    /// Whether the point lies inside, points on the maximum faces are outside.
+    pub fn contains(&self, point: Vec3d) -> bool {
+        (self.min.x..self.max.x).contains(&point.x)
+            && (self.min.y..self.max.y).contains(&point.y)
+            && (self.min.z..self.max.z).contains(&point.z)
+    }
+    /// Whether both boxes overlap, touching faces don't count.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x < other.max.x
+            && self.max.x > other.min.x
+            && self.min.y < other.max.y
+            && self.max.y > other.min.y
+            && self.min.z < other.max.z
+            && self.max.z > other.min.z
+    }
+}
+
+const fn pack_bits(value: i32, bits: u32, shift: u32) -> i64 {
+    (value as i64 & ((1 << bits) - 1)) << shift
+}
+
+/// Sign-extends the `bits` wide value at `shift`.
+const fn unpack_bits(value: i64, bits: u32, shift: u32) -> i32 {
+    (value << (64 - bits - shift) >> (64 - bits)) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packing() {
+        // Example from the protocol documentation
+        let position = BlockPos::new(18357644, 831, -20882616);
+        assert_eq!(position.pack(), 0x4607632C15B4833F);
+        assert_eq!(BlockPos::unpack(0x4607632C15B4833F), position);
+
+        let extremes = [
+            BlockPos::new(-1, -1, -1),
+            BlockPos::new(-(1 << 25), -(1 << 11), -(1 << 25)),
+            BlockPos::new((1 << 25) - 1, (1 << 11) - 1, (1 << 25) - 1),
+            BlockPos::new(-30_000_000, -64, 29_999_999),
+        ];
+        for position in extremes {
+            assert_eq!(BlockPos::unpack(position.pack()), position);
+        }
+        assert_eq!(BlockPos::new(-1, -1, -1).pack(), -1);
+
+        for section in [
+            SectionPos::new(-1, -4, 1),
+            SectionPos::new(-(1 << 21), -(1 << 19), (1 << 21) - 1),
+        ] {
+            assert_eq!(SectionPos::unpack(section.pack()), section);
+        }
+        let chunk = ChunkPos::new(-2, 7);
+        assert_eq!(chunk.pack(), 0x0000_0007_FFFF_FFFE);
+        assert_eq!(ChunkPos::unpack(chunk.pack()), chunk);
+    }
+
+    #[test]
+    fn conversions() {
+        let position = BlockPos::new(-1, -65, 16);
+        assert_eq!(position.chunk(), ChunkPos::new(-1, 1));
+        assert_eq!(position.section(), SectionPos::new(-1, -5, 1));
+        assert_eq!(position.section_relative(), (15, 15, 0));
+        assert_eq!(position.section().min_block(), BlockPos::new(-16, -80, 16));
+        assert_eq!(
+            ChunkPos::from(position).min_block(0),
+            BlockPos::new(-16, 0, 16)
+        );
+        assert_eq!(
+            BlockPos::containing(Vec3d::new(-0.5, 64.0, 15.99)),
+            BlockPos::new(-1, 64, 15)
+        );
+        assert_eq!(position.center().block_pos(), position);
+
+        assert_eq!(
+            position.neighbor(Direction::North),
+            BlockPos::new(-1, -65, 15)
+        );
+        for (neighbor, direction) in position.neighbors().into_iter().zip(Direction::ALL) {
+            assert_eq!(neighbor.neighbor(direction.opposite()), position);
+            assert_eq!(neighbor.distance_squared(position), 1);
+        }
+        assert_eq!(ChunkPos::new(0, 0).distance(ChunkPos::new(-3, 2)), 3);
+    }
+
+    #[test]
+    fn ordering() {
+        let mut positions = [
+            BlockPos::new(1, 0, 0),
+            BlockPos::new(0, 1, 0),
+            BlockPos::new(0, 0, 1),
+            BlockPos::new(-1, 0, 0),
+        ];
+        positions.sort();
+        assert_eq!(
+            positions,
+            [
+                BlockPos::new(-1, 0, 0),
+                BlockPos::new(1, 0, 0),
+                BlockPos::new(0, 0, 1),
+                BlockPos::new(0, 1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let block = Aabb::of_block(BlockPos::new(0, 0, 0));
+        assert!(block.contains(Vec3d::new(0.5, 0.0, 0.99)));
+        assert!(!block.contains(Vec3d::new(1.0, 0.5, 0.5)));
+        assert_eq!(block.center(), Vec3d::new(0.5, 0.5, 0.5));
+
+        let entity = Aabb::of_entity(Vec3d::new(1.5, 0.0, 0.5), 1.0, 1.8);
+        assert!(!block.intersects(&entity));
+        assert!(block.intersects(&entity.inflate(0.1)));
+        assert!(block.intersects(&entity.offset(Vec3d::new(-0.1, 0.0, 0.0))));
+        assert_eq!(
+            Aabb::new(Vec3d::new(1.0, 2.0, 3.0), Vec3d::ZERO).size(),
+            Vec3d::new(1.0, 2.0, 3.0)
+        );
+    }
+}
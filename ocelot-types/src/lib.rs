@@ -1,3 +1,5 @@
+pub mod budget;
+pub mod coordinates;
 pub mod text;
 
 use std::{
@@ -36,6 +38,15 @@ impl<const MAX: u64> BoundedString<MAX> {
 
 #[derive(Serialize, Deserialize)]
 pub struct VarInt(pub i32);
+impl VarInt {
+    pub const MAX_SIZE: usize = 5;
+
+    /// Number of bytes the value takes on the wire.
+    pub fn encoded_len(&self) -> usize {
+        let bits = 32 - (self.0 as u32).leading_zeros() as usize;
+        bits.div_ceil(7).max(1)
+    }
+}
 impl CustomType for VarInt {
     fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
         let mut value = 0;
@@ -44,8 +55,8 @@ impl CustomType for VarInt {
         loop {
             reader.read_exact(&mut byte)?;
             let current_byte = byte[0];
-            value |= ((current_byte & SEGMENT_BITS as u8) as i32) << position;
-            if (current_byte & CONTINUE_BITS as u8) == 0 {
+            value |= ((current_byte & SEGMENT_BITS) as i32) << position;
+            if (current_byte & CONTINUE_BITS) == 0 {
                 break;
             }
             position += 7;
@@ -65,13 +76,22 @@ impl CustomType for VarInt {
                 writer.write_all(&[value as u8])?;
                 return Ok(());
             }
-            writer.write_all(&[((value & SEGMENT_BITS as u32) as u8) | CONTINUE_BITS as u8])?;
+            writer.write_all(&[((value & SEGMENT_BITS as u32) as u8) | CONTINUE_BITS])?;
             value >>= 7;
         }
     }
 }
 
 pub struct VarLong(pub i64);
+impl VarLong {
+    pub const MAX_SIZE: usize = 10;
+
+    /// Number of bytes the value takes on the wire.
+    pub fn encoded_len(&self) -> usize {
+        let bits = 64 - (self.0 as u64).leading_zeros() as usize;
+        bits.div_ceil(7).max(1)
+    }
+}
 impl CustomType for VarLong {
     fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
         let mut value = 0;
@@ -80,8 +100,8 @@ impl CustomType for VarLong {
         loop {
             reader.read_exact(&mut byte)?;
             let current_byte = byte[0];
-            value |= ((current_byte & SEGMENT_BITS as u8) as i64) << position;
-            if (current_byte & CONTINUE_BITS as u8) == 0 {
+            value |= ((current_byte & SEGMENT_BITS) as i64) << position;
+            if (current_byte & CONTINUE_BITS) == 0 {
                 break;
             }
             position += 7;
@@ -101,7 +121,7 @@ impl CustomType for VarLong {
                 writer.write_all(&[value as u8])?;
                 return Ok(());
             }
-            writer.write_all(&[((value & SEGMENT_BITS as u64) as u8) | CONTINUE_BITS as u8])?;
+            writer.write_all(&[((value & SEGMENT_BITS as u64) as u8) | CONTINUE_BITS])?;
             value >>= 7;
         }
     }
@@ -113,6 +133,7 @@ pub enum ResourceLocationError {
     Invalid { namespace: String, path: String },
 }
 
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct ResourceLocation {
     namespace: String,
     path: String,
@@ -129,14 +150,17 @@ impl ResourceLocation {
         if regex.captures(&format!("{}:{}", namespace, path)).is_none() {
             return Err(ResourceLocationError::Invalid { namespace, path });
         }
-        Ok(Self {
-            namespace: namespace,
-            path: path.into(),
-        })
+        Ok(Self { namespace, path })
     }
     pub fn from_vanilla(path: impl Into<String>) -> Result<Self, ResourceLocationError> {
         Self::from("minecraft", path.into())
     }
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
 }
 
 impl Display for ResourceLocation {
@@ -176,6 +200,7 @@ impl<'de> Deserialize<'de> for ResourceLocation {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use crate::{CustomType, VarInt, VarLong};
 
@@ -206,6 +231,7 @@ mod tests {
             let mut buffer = Vec::new();
             varint.write_to(&mut buffer).unwrap();
             assert_eq!(buffer, expected);
+            assert_eq!(varint.encoded_len(), expected.len());
         };
         write_check(VarInt(0), &vec![0x00]);
         write_check(VarInt(1), &vec![0x01]);
@@ -256,6 +282,7 @@ mod tests {
             let mut buffer = Vec::new();
             varint.write_to(&mut buffer).unwrap();
             assert_eq!(buffer, expected);
+            assert_eq!(varint.encoded_len(), expected.len());
         };
         write_check(VarLong(0), &vec![0x00]);
         write_check(VarLong(1), &vec![0x01]);
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{ResourceLocation, text::private::ComponentAccess};
 
@@ -55,8 +56,13 @@ pub trait GenericComponent: ComponentAccess + Sized {
         self.access_component().hover_event = Some(hover_event);
         self
     }
+    /// Adds a child that inherits the style of this component.
+    fn append(mut self, child: TextComponent) -> Self {
+        self.access_component().extra.push(child);
+        self
+    }
 }
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct TextComponent {
     #[serde(flatten)]
     content: Content,
@@ -86,6 +92,12 @@ pub struct TextComponent {
     hover_event: Option<HoverEvent>,
 }
 impl TextComponent {
+    pub fn new(content: Content) -> Self {
+        Self {
+            content,
+            ..Default::default()
+        }
+    }
     pub fn text(text: impl Into<String>) -> Self {
         Self {
             content: Content::Text { text: text.into() },
@@ -106,6 +118,98 @@ impl TextComponent {
             ..Default::default()
         }
     }
+    pub fn score(name: impl Into<String>, objective: impl Into<String>) -> Self {
+        Self::new(Content::Score {
+            score: Score {
+                name: name.into(),
+                objective: objective.into(),
+            },
+        })
+    }
+    pub fn selector(selector: impl Into<String>) -> Self {
+        Self::new(Content::Selector {
+            selector: selector.into(),
+            separator: None,
+        })
+    }
+    pub fn get_content(&self) -> &Content {
+        &self.content
+    }
+    pub fn get_extra(&self) -> &[TextComponent] {
+        &self.extra
+    }
+    pub fn get_color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+    pub fn get_font(&self) -> Option<&ResourceLocation> {
+        self.font.as_ref()
+    }
+    pub fn get_bold(&self) -> Option<bool> {
+        self.bold
+    }
+    pub fn get_italic(&self) -> Option<bool> {
+        self.italic
+    }
+    pub fn get_underlined(&self) -> Option<bool> {
+        self.underlined
+    }
+    pub fn get_strikethrough(&self) -> Option<bool> {
+        self.strikethrough
+    }
+    pub fn get_obfuscated(&self) -> Option<bool> {
+        self.obfuscated
+    }
+    pub fn get_shadow_color(&self) -> Option<&ShadowColor> {
+        self.shadow_color.as_ref()
+    }
+    pub fn get_insertion(&self) -> Option<&str> {
+        self.insertion.as_deref()
+    }
+    pub fn get_click_event(&self) -> Option<&ClickEvent> {
+        self.click_event.as_ref()
+    }
+    pub fn get_hover_event(&self) -> Option<&HoverEvent> {
+        self.hover_event.as_ref()
+    }
+    /// Whether the component is plain text without style or children, which vanilla shortens to a string.
+    pub fn is_plain(&self) -> bool {
+        matches!(self.content, Content::Text { .. })
+            && self.extra.is_empty()
+            && self.color.is_none()
+            && self.font.is_none()
+            && self.bold.is_none()
+            && self.italic.is_none()
+            && self.underlined.is_none()
+            && self.strikethrough.is_none()
+            && self.obfuscated.is_none()
+            && self.shadow_color.is_none()
+            && self.insertion.is_none()
+            && self.click_event.is_none()
+            && self.hover_event.is_none()
+    }
+    /// Flattens the component and its children into unformatted text.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        self.append_plain_text(&mut text);
+        text
+    }
+    fn append_plain_text(&self, text: &mut String) {
+        match &self.content {
+            Content::Text { text: content } => text.push_str(content),
+            Content::Translatable {
+                translate,
+                fallback,
+                ..
+            } => text.push_str(fallback.as_ref().unwrap_or(translate)),
+            Content::Selector { selector, .. } => text.push_str(selector),
+            Content::Keybind { keybind } => text.push_str(keybind),
+            // Resolved by the server, they have no text of their own.
+            Content::Score { .. } | Content::Nbt { .. } | Content::Object { .. } => {}
+        }
+        self.extra
+            .iter()
+            .for_each(|extra| extra.append_plain_text(text));
+    }
 }
 impl ComponentAccess for TextComponent {
     fn access_component(&mut self) -> &mut TextComponent {
@@ -114,7 +218,8 @@ impl ComponentAccess for TextComponent {
 }
 impl GenericComponent for TextComponent {}
 
-#[derive(Serialize, Deserialize)]
+/// What a component shows, vanilla tells the variants apart by their fields.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(untagged)]
 pub enum Content {
     Text {
@@ -127,9 +232,30 @@ pub enum Content {
         #[serde(skip_serializing_if = "Option::is_none")]
         with: Option<Vec<TextComponent>>,
     },
+    Score {
+        score: Score,
+    },
+    Selector {
+        selector: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        separator: Option<Box<TextComponent>>,
+    },
     Keybind {
         keybind: String,
     },
+    Nbt {
+        nbt: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interpret: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        separator: Option<Box<TextComponent>>,
+        #[serde(flatten)]
+        source: NbtSource,
+    },
+    Object {
+        #[serde(flatten)]
+        object: ObjectInfo,
+    },
 }
 impl Default for Content {
     fn default() -> Self {
@@ -139,13 +265,114 @@ impl Default for Content {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The score of an entity or a fake player in an objective, resolved by the server.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Score {
+    /// A player name, a UUID or a selector matching a single entity.
+    pub name: String,
+    pub objective: String,
+}
+
+/// Where the path of an NBT component is looked up.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum NbtSource {
+    /// Coordinates of a block entity, e.g. `~ ~-1 ~`.
+    Block {
+        block: String,
+    },
+    /// A selector of the entities.
+    Entity {
+        entity: String,
+    },
+    Storage {
+        storage: ResourceLocation,
+    },
+}
+
+/// A sprite drawn in place of text.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "object", rename_all = "snake_case")]
+pub enum ObjectInfo {
+    Atlas {
+        /// Defaults to `minecraft:blocks`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        atlas: Option<ResourceLocation>,
+        sprite: ResourceLocation,
+    },
+    /// The head of a player.
+    Player {
+        player: PlayerProfile,
+        /// Defaults to `true`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hat: Option<bool>,
+    },
+}
+
+/// A game profile that may still have to be resolved, vanilla also accepts just the name.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(from = "ProfileRepr")]
+pub struct PlayerProfile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "int_array_uuid::option"
+    )]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<ProfileProperty>,
+}
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProfileRepr {
+    Name(String),
+    Full {
+        name: Option<String>,
+        #[serde(default, with = "int_array_uuid::option")]
+        id: Option<Uuid>,
+        #[serde(default)]
+        properties: Vec<ProfileProperty>,
+    },
+}
+impl From<ProfileRepr> for PlayerProfile {
+    fn from(repr: ProfileRepr) -> Self {
+        match repr {
+            ProfileRepr::Name(name) => Self {
+                name: Some(name),
+                ..Default::default()
+            },
+            ProfileRepr::Full {
+                name,
+                id,
+                properties,
+            } => Self {
+                name,
+                id,
+                properties,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
 pub enum ShadowColor {
+    /// ARGB
     Int(i32),
+    /// RGBA, each from 0 to 1
     FloatArray([f32; 4]),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(tag = "action", rename_all = "snake_case")]
 pub enum ClickEvent {
     OpenUrl {
@@ -166,17 +393,103 @@ pub enum ClickEvent {
     CopyToClipboard {
         value: String,
     },
-    //ShowDialog {},
+    ShowDialog {
+        dialog: Dialog,
+    },
     Custom {
         id: ResourceLocation,
         #[serde(skip_serializing_if = "Option::is_none")]
         payload: Option<serde_json::Value>,
     },
 }
-#[derive(Serialize, Deserialize)]
+
+/// A dialog of the `minecraft:dialog` registry or one defined inline.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum Dialog {
+    Id(ResourceLocation),
+    Inline(serde_json::Value),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(tag = "action", rename_all = "snake_case")]
 pub enum HoverEvent {
-    ShowText { value: Box<TextComponent> },
+    ShowText {
+        value: Box<TextComponent>,
+    },
+    ShowItem {
+        id: ResourceLocation,
+        #[serde(default = "HoverEvent::default_count")]
+        count: i32,
+        /// Changes to the default components of the item, keyed by component type.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        components: Option<serde_json::Value>,
+    },
+    ShowEntity {
+        id: ResourceLocation,
+        #[serde(with = "int_array_uuid")]
+        uuid: Uuid,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<Box<TextComponent>>,
+    },
+}
+impl HoverEvent {
+    fn default_count() -> i32 {
+        1
+    }
+}
+
+/// Vanilla writes UUIDs as four ints, but also reads them as strings.
+mod int_array_uuid {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+    use uuid::Uuid;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Ints([i32; 4]),
+        String(String),
+    }
+
+    pub fn serialize<S: Serializer>(uuid: &Uuid, serializer: S) -> Result<S::Ok, S::Error> {
+        let bits = uuid.as_u128();
+        [96, 64, 32, 0]
+            .map(|shift| (bits >> shift) as i32)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Ints(ints) => Ok(Uuid::from_u128(
+                ints.iter()
+                    .fold(0, |bits, int| (bits << 32) | *int as u32 as u128),
+            )),
+            Repr::String(string) => Uuid::parse_str(&string).map_err(de::Error::custom),
+        }
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use uuid::Uuid;
+
+        pub fn serialize<S: Serializer>(
+            uuid: &Option<Uuid>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match uuid {
+                Some(uuid) => super::serialize(uuid, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Uuid>, D::Error> {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] Uuid);
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(uuid)| uuid))
+        }
+    }
 }
 
 #[derive(Default)]
@@ -210,3 +523,98 @@ impl ComponentAccess for TranslatableBuilder {
     }
 }
 impl GenericComponent for TranslatableBuilder {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn round_trip(json: serde_json::Value) -> TextComponent {
+        let component: TextComponent = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&component).unwrap(), json);
+        component
+    }
+
+    #[test]
+    fn content_types() {
+        let component = round_trip(json!({"score": {"name": "@s", "objective": "deaths"}}));
+        assert_eq!(component, TextComponent::score("@s", "deaths"));
+        let component = round_trip(json!({"selector": "@a", "separator": {"text": ", "}}));
+        assert_eq!(component.plain_text(), "@a");
+        let component = round_trip(json!({"nbt": "Items", "interpret": true, "entity": "@p"}));
+        assert!(matches!(
+            component.get_content(),
+            Content::Nbt {
+                source: NbtSource::Entity { .. },
+                ..
+            }
+        ));
+        round_trip(json!({"nbt": "quest", "storage": "ocelot:quests"}));
+        round_trip(json!({"object": "atlas", "sprite": "minecraft:block/stone"}));
+        let component = round_trip(json!({
+            "object": "player",
+            "player": {"name": "Steve", "id": [1, 2, -1, 4]},
+            "hat": false,
+        }));
+        let Content::Object {
+            object: ObjectInfo::Player { player, .. },
+        } = component.get_content()
+        else {
+            panic!("Expected a player object!");
+        };
+        assert_eq!(
+            player.id,
+            Some(Uuid::from_u128(0x0000_0001_0000_0002_FFFF_FFFF_0000_0004))
+        );
+
+        // A profile may be just a name
+        let component: TextComponent =
+            serde_json::from_value(json!({"object": "player", "player": "Alex"})).unwrap();
+        assert_eq!(component.plain_text(), "");
+    }
+
+    #[test]
+    fn events() {
+        round_trip(json!({
+            "text": "Zombie",
+            "shadow_color": -16777216,
+            "hover_event": {
+                "action": "show_entity",
+                "id": "minecraft:zombie",
+                "uuid": [1, 2, -1, 4],
+                "name": {"text": "Bob"},
+            },
+            "click_event": {"action": "show_dialog", "dialog": "minecraft:server_links"},
+        }));
+        round_trip(json!({
+            "text": "Sword",
+            "shadow_color": [1.0, 0.5, 0.25, 1.0],
+            "hover_event": {
+                "action": "show_item",
+                "id": "minecraft:diamond_sword",
+                "count": 1,
+                "components": {"minecraft:damage": 5},
+            },
+            "click_event": {"action": "show_dialog", "dialog": {"type": "minecraft:notice"}},
+        }));
+
+        // UUIDs may be strings and the count defaults to one
+        let hover_event: HoverEvent = serde_json::from_value(json!({
+            "action": "show_entity",
+            "id": "minecraft:zombie",
+            "uuid": "00000001-0000-0002-ffff-ffff00000004",
+        }))
+        .unwrap();
+        assert!(matches!(
+            hover_event,
+            HoverEvent::ShowEntity { name: None, .. }
+        ));
+        let hover_event: HoverEvent = serde_json::from_value(json!({
+            "action": "show_item",
+            "id": "minecraft:stone",
+        }))
+        .unwrap();
+        assert!(matches!(hover_event, HoverEvent::ShowItem { count: 1, .. }));
+    }
+}
@@ -0,0 +1,581 @@
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use ocelot_data::registry::SYNCED_REGISTRIES;
+use ocelot_protocol::{
+    codec::{
+        BoundedPrefixedArray, Json, MinecraftCodec, Nbt, NbtText, PrefixedArray, PrefixedBytes,
+    },
+    frame::{Frame, PacketStream},
+    packet::{
+        ConnectionState, MinecraftPacket, PacketEnum,
+        configuration::{
+            clientbound as configuration_clientbound, serverbound::ServerboundConfiguration,
+        },
+        handshaking::serverbound::ServerboundHandshaking,
+        login::{clientbound as login_clientbound, serverbound::ServerboundLogin},
+        play::{clientbound as play_clientbound, serverbound::ServerboundPlay},
+        status::{clientbound as status_clientbound, serverbound::ServerboundStatus},
+        types::{GameEvent, GameMode, Intent, KnownPack, Properties, RegistryEntry, TeleportFlags},
+    },
+    version::{self, ProtocolVersion},
+};
+use ocelot_types::{BoundedString, ResourceLocation, VarInt, text::TextComponent};
+use openssl::rsa::Padding;
+use rand::{RngCore, SeedableRng};
+use tokio::net::TcpStream;
+
+use crate::{
+    legacy_ping,
+    player::{self, Player},
+    server::Server,
+    session::{self, ProfileProperty},
+    status,
+};
+
+// The written code here is only a proof of concept and for testing purposes.
+
+fn format_packet_name(full_packet_name: &str) -> String {
+    let mut packet_name = full_packet_name
+        .split("::")
+        .last()
+        .unwrap_or(full_packet_name);
+    packet_name = packet_name
+        .strip_prefix("Clientbound")
+        .unwrap_or(packet_name);
+    packet_name = packet_name
+        .strip_prefix("Serverbound")
+        .unwrap_or(packet_name);
+    let mut final_packet_name = String::new();
+    for (i, c) in packet_name
+        .strip_suffix("Packet")
+        .unwrap_or(packet_name)
+        .chars()
+        .enumerate()
+    {
+        if i > 0 && c.is_uppercase() {
+            final_packet_name.push(' ');
+        }
+        final_packet_name.push(c);
+    }
+    final_packet_name
+}
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+pub struct Connection {
+    id: u64,
+    state: ConnectionState,
+    server: Arc<Server>,
+    address: SocketAddr,
+    player: Player,
+    protocol_version: i32,
+    version: &'static ProtocolVersion,
+    verify_token: [u8; 4],
+}
+impl Connection {
+    pub fn new(server: Arc<Server>, address: SocketAddr) -> Self {
+        let mut rng = rand::rngs::StdRng::from_os_rng();
+        let mut verify_token = [0; 4];
+        rng.fill_bytes(&mut verify_token);
+        Self {
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            state: ConnectionState::Handshaking,
+            server,
+            address,
+            player: Player {
+                username: None,
+                uuid: None,
+            },
+            verify_token,
+            protocol_version: 0,
+            version: version::latest(),
+        }
+    }
+    async fn send_packet<P: MinecraftPacket>(
+        &self,
+        packet: &P,
+        stream: &mut PacketStream<TcpStream>,
+    ) -> io::Result<()> {
+        stream.write_packet_with_id(packet, P::id_for(self.version)?)?;
+        println!(
+            "[Server -> Client] {} (State: {}, ID: {})",
+            format_packet_name(std::any::type_name::<P>()),
+            self.state,
+            packet.get_id()
+        );
+        Ok(())
+    }
+    fn read_packet<P: PacketEnum>(&self, frame: &Frame) -> io::Result<Option<P>> {
+        let mut buffer = frame.buffer().lenient(self.server.config.lenient_decoding);
+        let Some(packet) = P::decode_for(self.version, frame.id, &mut buffer)? else {
+            eprintln!(
+                "[Client -> Server] ??? (State: {}, ID: {})",
+                self.state, frame.id
+            );
+            return Ok(None);
+        };
+        println!(
+            "[Client -> Server] {} (State: {}, ID: {})",
+            format_packet_name(packet.name()),
+            self.state,
+            packet.get_id()
+        );
+        if buffer.remaining() > 0 {
+            eprintln!(
+                "{}: ignoring {} trailing bytes at {}",
+                format_packet_name(packet.name()),
+                buffer.remaining(),
+                buffer.position()
+            );
+        }
+        Ok(Some(packet))
+    }
+    fn unsupported_version_reason(&self) -> TextComponent {
+        let latest = version::latest();
+        if self.protocol_version < version::oldest().protocol {
+            TextComponent::translate("multiplayer.disconnect.outdated_client")
+                .with_fallback(format!("Outdated client! Please use {}", latest.name))
+                .with_args(vec![TextComponent::text(latest.name)])
+                .build()
+        } else {
+            TextComponent::translate("multiplayer.disconnect.outdated_server")
+                .with_fallback(format!("Outdated server! I'm still on {}", latest.name))
+                .with_args(vec![TextComponent::text(latest.name)])
+                .build()
+        }
+    }
+    fn identity(&self) -> String {
+        match (&self.player.username, &self.player.uuid) {
+            (Some(username), Some(uuid)) => format!("{} ({}, {})", username, uuid, self.address),
+            (Some(username), None) => format!("{} ({})", username, self.address),
+            _ => self.address.to_string(),
+        }
+    }
+    /// Sends the disconnect packet of the current state and closes the connection.
+    pub async fn disconnect(
+        &mut self,
+        stream: &mut PacketStream<TcpStream>,
+        reason: TextComponent,
+    ) {
+        println!("Disconnecting {}: {}", self.identity(), reason.plain_text());
+        let result = match self.state {
+            // Neither state has a packet to tell the client why it is disconnected.
+            ConnectionState::Handshaking | ConnectionState::Status => Ok(()),
+            ConnectionState::Login => {
+                let disconnect_packet = login_clientbound::DisconnectPacket::new(Json(reason));
+                self.send_packet(&disconnect_packet, stream).await
+            }
+            ConnectionState::Configuration => {
+                let disconnect_packet =
+                    configuration_clientbound::DisconnectPacket::new(NbtText(reason));
+                self.send_packet(&disconnect_packet, stream).await
+            }
+            ConnectionState::Play => {
+                let disconnect_packet = play_clientbound::DisconnectPacket::new(NbtText(reason));
+                self.send_packet(&disconnect_packet, stream).await
+            }
+        };
+        if let Err(error) = result {
+            eprintln!(
+                "Failed to send disconnect packet to {}: {}",
+                self.identity(),
+                error
+            );
+        }
+        let _ = stream.shutdown().await;
+    }
+    async fn enable_compression(&mut self, stream: &mut PacketStream<TcpStream>) -> io::Result<()> {
+        if let Some(threshold) = self.server.config.compression_threshold {
+            let set_compression_packet =
+                login_clientbound::SetCompressionPacket::new(VarInt(threshold as i32));
+            self.send_packet(&set_compression_packet, stream).await?;
+            stream.enable_compression(threshold);
+        }
+        Ok(())
+    }
+    async fn finish_login(
+        &mut self,
+        stream: &mut PacketStream<TcpStream>,
+        properties: Vec<Properties>,
+    ) -> io::Result<()> {
+        self.enable_compression(stream).await?;
+        let login_success = login_clientbound::LoginSuccessPacket::new(
+            self.player.uuid.unwrap(),
+            BoundedString::new(self.player.username.as_ref().unwrap())?,
+            BoundedPrefixedArray::new(properties),
+        );
+        self.send_packet(&login_success, stream).await
+    }
+    pub async fn handle_connection(&mut self, mut stream: TcpStream) {
+        let mut first_byte = [0u8; 1];
+        match stream.peek(&mut first_byte).await {
+            Ok(1) if first_byte[0] == legacy_ping::LEGACY_PING_ID => {
+                let status = self.server.status_provider.status();
+                if let Err(error) = legacy_ping::handle_legacy_ping(&mut stream, &status).await {
+                    eprintln!("Failed to answer legacy ping: {}", error);
+                }
+                return;
+            }
+            Ok(1) => {}
+            _ => return,
+        }
+        let mut stream = PacketStream::new(stream);
+        loop {
+            let frame = match stream.read_frame().await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(error) => {
+                    eprintln!("Failed to read packet from {}: {}", self.identity(), error);
+                    break;
+                }
+            };
+            let result = match self.handle_packet(&mut stream, frame).await {
+                Ok(keep_reading) => stream.flush().await.map(|_| keep_reading),
+                Err(error) => Err(error),
+            };
+            match result {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => {
+                    let reason = TextComponent::translate("disconnect.genericReason")
+                        .with_fallback(format!("Internal Exception: {}", error))
+                        .with_args(vec![TextComponent::text(error.to_string())])
+                        .build();
+                    self.disconnect(&mut stream, reason).await;
+                    break;
+                }
+            }
+        }
+        if let ConnectionState::Play = self.state
+            && let Some(uuid) = self.player.uuid
+        {
+            self.server.players.remove(&uuid, self.id);
+        }
+    }
+    /// Returns whether the connection should keep reading packets.
+    async fn handle_packet(
+        &mut self,
+        stream: &mut PacketStream<TcpStream>,
+        frame: Frame,
+    ) -> io::Result<bool> {
+        let server = Arc::clone(&self.server);
+        let rsa_key_pair = &server.rsa_key_pair;
+        match self.state {
+            ConnectionState::Handshaking => {
+                match self.read_packet::<ServerboundHandshaking>(&frame)? {
+                    Some(ServerboundHandshaking::Handshake(packet)) => {
+                        println!("Packet Data:");
+                        println!("Protocol Version: {}", packet.get_protocol_version().0);
+                        println!("Server Address: {}", packet.get_server_address().0);
+                        println!("Server Port: {}", packet.get_server_port());
+                        println!("Intent: {}", packet.get_intent());
+
+                        self.protocol_version = packet.get_protocol_version().0;
+                        let version = version::find(self.protocol_version);
+                        if let Some(version) = version {
+                            self.version = version;
+                        }
+                        match packet.get_intent() {
+                            Intent::Status => self.state = ConnectionState::Status,
+                            Intent::Login | Intent::Transfer => {
+                                self.state = ConnectionState::Login;
+                                if version.is_none() {
+                                    let reason = self.unsupported_version_reason();
+                                    self.disconnect(stream, reason).await;
+                                    return Ok(false);
+                                }
+                            }
+                        }
+                    }
+                    None => {}
+                }
+            }
+            ConnectionState::Status => match self.read_packet::<ServerboundStatus>(&frame)? {
+                Some(ServerboundStatus::StatusRequest(_)) => {
+                    let mut status = self.server.status_provider.status();
+                    if let Some(version) = version::find(self.protocol_version) {
+                        // Every supported client should see the server as compatible.
+                        status.version.protocol = VarInt(version.protocol);
+                    }
+                    status::limit_response_size(&mut status);
+                    let status_response_packet =
+                        status_clientbound::StatusResponsePacket::new(Json(status));
+                    self.send_packet(&status_response_packet, stream).await?;
+                }
+                Some(ServerboundStatus::PingRequest(packet)) => {
+                    let pong_response_packet =
+                        status_clientbound::PongResponsePacket::new(*packet.get_timestamp());
+                    self.send_packet(&pong_response_packet, stream).await?;
+                    return Ok(false);
+                }
+                None => {}
+            },
+            ConnectionState::Login => match self.read_packet::<ServerboundLogin>(&frame)? {
+                Some(ServerboundLogin::LoginStart(packet)) => {
+                    println!("Packet Data:");
+                    println!("Name: {}", packet.get_name().0);
+                    println!("Player UUID: {}", packet.get_player_uuid());
+
+                    let username = &packet.get_name().0;
+                    if !server.config.online_mode && !player::is_valid_username(username) {
+                        let reason = TextComponent::text(format!(
+                            "Invalid username! Usernames have to be {} to {} characters of A-Z, a-z, 0-9 and _.",
+                            player::MIN_USERNAME_LENGTH,
+                            player::MAX_USERNAME_LENGTH
+                        ));
+                        self.disconnect(stream, reason).await;
+                        return Ok(false);
+                    }
+                    self.player.username = Some(username.clone());
+                    // Offline players can't prove who they are, so the client's UUID is never trusted.
+                    self.player.uuid = Some(player::offline_uuid(username));
+
+                    if server.config.online_mode {
+                        let encryption_request_packet =
+                            login_clientbound::EncryptionRequestPacket::new(
+                                BoundedString::new("")?,
+                                PrefixedBytes(rsa_key_pair.public_key_to_der()?),
+                                PrefixedBytes(self.verify_token.to_vec()),
+                                true,
+                            );
+                        self.send_packet(&encryption_request_packet, stream).await?;
+                    } else {
+                        self.finish_login(stream, Vec::new()).await?;
+                    }
+                }
+                Some(ServerboundLogin::EncryptionResponse(packet)) => {
+                    if !server.config.online_mode || self.player.username.is_none() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Unexpected encryption response!",
+                        ));
+                    }
+                    let shared_secret = &packet.get_shared_secret().0;
+                    let verify_token = &packet.get_verify_token().0;
+                    println!("Packet Data:");
+                    println!("Shared Secret: {:?}", packet.get_shared_secret().0);
+                    println!("Verify Token: {:?}", packet.get_verify_token().0);
+                    let mut decrypted_shared_secret = [0; 128];
+                    let shared_secret_length = rsa_key_pair.private_decrypt(
+                        shared_secret,
+                        &mut decrypted_shared_secret,
+                        Padding::PKCS1,
+                    )?;
+                    if shared_secret_length != 16 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Invalid shared secret length!",
+                        ));
+                    }
+                    let decrypted_shared_secret = &decrypted_shared_secret[..16];
+                    println!("Decrypted Shared Secret: {:?}", decrypted_shared_secret);
+                    let mut decrypted_verify_token = [0; 128];
+                    let verify_token_length = rsa_key_pair.private_decrypt(
+                        verify_token,
+                        &mut decrypted_verify_token,
+                        Padding::PKCS1,
+                    )?;
+                    println!("Decrypted Verify Token: {:?}", decrypted_verify_token);
+                    if decrypted_verify_token[..verify_token_length] != self.verify_token {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Invalid verify token!",
+                        ));
+                    }
+
+                    stream.enable_encryption(decrypted_shared_secret)?;
+                    let server_hash = session::get_server_hash(
+                        "",
+                        decrypted_shared_secret,
+                        &rsa_key_pair.public_key_to_der()?,
+                    );
+                    let username = self.player.username.clone().unwrap();
+                    match server
+                        .session_service
+                        .has_joined(&username, &server_hash)
+                        .await
+                    {
+                        Ok(Some(profile)) => {
+                            let properties = profile
+                                .properties
+                                .iter()
+                                .map(ProfileProperty::to_properties)
+                                .collect::<io::Result<Vec<_>>>()?;
+                            self.player.uuid = Some(profile.id);
+                            self.player.username = Some(profile.name);
+                            self.finish_login(stream, properties).await?;
+                        }
+                        Ok(None) => {
+                            let reason = TextComponent::translate(
+                                "multiplayer.disconnect.unverified_username",
+                            )
+                            .with_fallback("Failed to verify username!")
+                            .build();
+                            self.disconnect(stream, reason).await;
+                            return Ok(false);
+                        }
+                        Err(error) => {
+                            eprintln!("Failed to reach the session server: {}", error);
+                            let reason = TextComponent::translate(
+                                "multiplayer.disconnect.authservers_down",
+                            )
+                            .with_fallback(
+                                "Authentication servers are down. Please try again later, sorry!",
+                            )
+                            .build();
+                            self.disconnect(stream, reason).await;
+                            return Ok(false);
+                        }
+                    }
+                }
+                Some(ServerboundLogin::LoginAcknowledged(_)) => {
+                    self.state = ConnectionState::Configuration;
+                }
+                _ => {}
+            },
+            ConnectionState::Configuration => {
+                match self.read_packet::<ServerboundConfiguration>(&frame)? {
+                    Some(ServerboundConfiguration::ClientInformation(packet)) => {
+                        println!("Packet Data:");
+                        println!("Locale: {}", packet.get_locale().0);
+                        println!("View Distance: {}", packet.get_view_distance());
+                        println!("Chat Mode: {}", packet.get_chat_mode());
+                        println!("Chat Colors: {}", packet.get_chat_colors());
+                        println!(
+                            "Displayed Skin Parts: {}",
+                            packet.get_displayed_skin_parts()
+                        );
+                        println!("Main Hand: {}", packet.get_main_hand());
+                        println!(
+                            "Enable text filtering: {}",
+                            packet.get_enable_text_filtering()
+                        );
+                        println!(
+                            "Allow server listings: {}",
+                            packet.get_allow_server_listings()
+                        );
+                        println!("Particle Status: {}", packet.get_particle_status());
+
+                        let known_packs_packet =
+                            configuration_clientbound::KnownPacksPacket::new(PrefixedArray(vec![
+                                KnownPack {
+                                    namespace: BoundedString::<_>::new("minecraft").unwrap(),
+                                    id: BoundedString::<_>::new("core").unwrap(),
+                                    version: BoundedString::<_>::new(self.version.name).unwrap(),
+                                },
+                            ]));
+                        self.send_packet(&known_packs_packet, stream).await?;
+                    }
+                    Some(ServerboundConfiguration::PluginMessage(packet)) => {
+                        println!("Packet Data:");
+                        println!("Channel: {}", packet.get_channel());
+                        println!("Data: {:?}", packet.get_data().0);
+                    }
+                    Some(ServerboundConfiguration::KnownPacks(packet)) => {
+                        println!("Packet Data:");
+                        println!("Known Packs:");
+                        for known_pack in &packet.get_known_packs().0 {
+                            println!("Namespace: {}", known_pack.namespace.0);
+                            println!("ID: {}", known_pack.id.0);
+                            println!("Version: {}", known_pack.version.0);
+                        }
+
+                        for registry in SYNCED_REGISTRIES {
+                            let mut entries = Vec::new();
+                            for entry in registry.entries {
+                                entries.push(RegistryEntry {
+                                    id: BoundedString::<32767>::new(entry.name)
+                                        .unwrap()
+                                        .0
+                                        .try_into()
+                                        .unwrap(),
+                                    data: Some(Nbt::decode(&mut &entry.nbt_bytes[..])?),
+                                });
+                            }
+                            let registry_data_packet =
+                                configuration_clientbound::RegistryDataPacket::new(
+                                    BoundedString::<32767>::new(registry.registry_id)
+                                        .unwrap()
+                                        .0
+                                        .try_into()
+                                        .unwrap(),
+                                    PrefixedArray(entries),
+                                );
+                            self.send_packet(&registry_data_packet, stream).await?;
+                        }
+
+                        let finish_configuration_packet =
+                            configuration_clientbound::FinishConfigurationPacket::new();
+                        self.send_packet(&finish_configuration_packet, stream)
+                            .await?;
+                    }
+                    Some(ServerboundConfiguration::AcknowledgeFinishConfiguration(_)) => {
+                        self.state = ConnectionState::Play;
+                        self.server.players.add(
+                            self.player.uuid.unwrap(),
+                            self.player.username.as_ref().unwrap(),
+                            self.id,
+                        );
+
+                        let login_packet = play_clientbound::LoginPacket::new(
+                            0,
+                            false,
+                            PrefixedArray(Vec::new()),
+                            VarInt(1),
+                            VarInt(8),
+                            VarInt(8),
+                            false,
+                            false,
+                            false,
+                            VarInt(0),
+                            ResourceLocation::from_vanilla("overworld").unwrap(),
+                            0,
+                            GameMode::Survival,
+                            GameMode::Undefined,
+                            false,
+                            false,
+                            None,
+                            VarInt(0),
+                            VarInt(60),
+                            false,
+                        );
+                        self.send_packet(&login_packet, stream).await?;
+                        let game_event_packet = play_clientbound::GameEventPacket::new(
+                            GameEvent::StartWaitingForLevelChunks,
+                            0.0,
+                        );
+                        self.send_packet(&game_event_packet, stream).await?;
+                        let synchronize_player_position_packet =
+                            play_clientbound::SynchronizePlayerPositionPacket::new(
+                                VarInt(1),
+                                0.0,
+                                -128.0,
+                                0.0,
+                                0.0,
+                                -128.0,
+                                0.0,
+                                0.0,
+                                0.0,
+                                TeleportFlags::empty(),
+                            );
+                        self.send_packet(&synchronize_player_position_packet, stream)
+                            .await?;
+                    }
+                    None => {}
+                }
+            }
+            ConnectionState::Play => match self.read_packet::<ServerboundPlay>(&frame)? {
+                Some(ServerboundPlay::ClientTickEnd(_)) => {}
+                None => {}
+            },
+        }
+        Ok(true)
+    }
+}
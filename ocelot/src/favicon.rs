@@ -0,0 +1,185 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+pub const FAVICON_SIZE: u32 = 64;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// How often [`Favicon::watch`] looks at the file's modification time.
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+struct CachedFavicon {
+    modified: SystemTime,
+    data_uri: Option<String>,
+}
+
+/// Server icon loaded from a PNG file, reloaded whenever the file's modification time changes.
+/// Reading the icon only looks at the cache, the file is checked by [`Favicon::reload`].
+pub struct Favicon {
+    path: PathBuf,
+    cache: Mutex<Option<CachedFavicon>>,
+}
+impl Favicon {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cache: Mutex::new(None),
+        }
+    }
+    /// Loads the icon and keeps reloading it every [`RELOAD_INTERVAL`] on the blocking pool,
+    /// until the last reference is dropped. Has to be called from within a Tokio runtime.
+    pub fn watch(path: impl Into<PathBuf>) -> Arc<Self> {
+        let favicon = Arc::new(Self::new(path));
+        favicon.reload();
+        let watched = Arc::downgrade(&favicon);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let Some(favicon) = watched.upgrade() else {
+                    break;
+                };
+                if tokio::task::spawn_blocking(move || favicon.reload())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        favicon
+    }
+    /// The icon as of the last reload.
+    pub fn data_uri(&self) -> Option<String> {
+        let cache = self.cache.lock().unwrap();
+        cache.as_ref().and_then(|cached| cached.data_uri.clone())
+    }
+    /// Loads the file again if its modification time changed, or forgets it if it's gone.
+    pub fn reload(&self) {
+        let Ok(modified) = fs::metadata(&self.path).and_then(|metadata| metadata.modified()) else {
+            *self.cache.lock().unwrap() = None;
+            return;
+        };
+        if let Some(cached) = self.cache.lock().unwrap().as_ref()
+            && cached.modified == modified
+        {
+            return;
+        }
+        // Read without holding the lock, so status requests don't wait for the file.
+        let data_uri = match Self::load(&self.path) {
+            Ok(data_uri) => Some(data_uri),
+            Err(error) => {
+                eprintln!("Failed to load favicon {}: {}", self.path.display(), error);
+                None
+            }
+        };
+        *self.cache.lock().unwrap() = Some(CachedFavicon { modified, data_uri });
+    }
+    fn load(path: &Path) -> io::Result<String> {
+        let bytes = fs::read(path)?;
+        Self::validate(&bytes)?;
+        Ok(format!("data:image/png;base64,{}", STANDARD.encode(&bytes)))
+    }
+    fn validate(bytes: &[u8]) -> io::Result<()> {
+        // The IHDR chunk always comes first and holds the dimensions right after its type.
+        if bytes.len() < 24 || bytes[..8] != PNG_SIGNATURE || &bytes[12..16] != b"IHDR" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Favicon is not a PNG image!",
+            ));
+        }
+        let width = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+        let height = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
+        if width != FAVICON_SIZE || height != FAVICON_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Favicon has to be {}x{} pixels, got {}x{}!",
+                    FAVICON_SIZE, FAVICON_SIZE, width, height
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    /// The signature and IHDR chunk of a PNG, which is all the validation looks at.
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = PNG_SIGNATURE.to_vec();
+        bytes.extend(13u32.to_be_bytes());
+        bytes.extend(b"IHDR");
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+        bytes.extend([8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    fn write(path: &Path, bytes: &[u8], modified: SystemTime) {
+        fs::write(path, bytes).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn validation() {
+        let icon = png(64, 64);
+        assert!(Favicon::validate(&icon).is_ok());
+        assert!(Favicon::validate(&png(64, 32)).is_err());
+        assert!(Favicon::validate(&png(128, 128)).is_err());
+        assert!(Favicon::validate(b"GIF89a\x40\x00\x40\x00 not a png at all").is_err());
+        assert!(Favicon::validate(&icon[..20]).is_err());
+    }
+
+    #[test]
+    fn reloads_changed_file() {
+        let path = std::env::temp_dir().join(format!("ocelot-favicon-{}.png", std::process::id()));
+        let favicon = Favicon::new(&path);
+        favicon.reload();
+        assert_eq!(favicon.data_uri(), None);
+
+        let icon = png(64, 64);
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        write(&path, &icon, modified);
+        // Nothing changes until the next reload
+        assert_eq!(favicon.data_uri(), None);
+        favicon.reload();
+        let data_uri = favicon.data_uri().unwrap();
+        assert_eq!(
+            data_uri,
+            format!("data:image/png;base64,{}", STANDARD.encode(&icon))
+        );
+
+        // Cached as long as the modification time stays the same
+        write(&path, &png(32, 32), modified);
+        favicon.reload();
+        assert_eq!(favicon.data_uri().unwrap(), data_uri);
+
+        write(&path, &png(32, 32), modified + Duration::from_secs(1));
+        favicon.reload();
+        assert_eq!(favicon.data_uri(), None);
+
+        let icon = [png(64, 64), vec![0; 16]].concat();
+        write(&path, &icon, modified + Duration::from_secs(2));
+        favicon.reload();
+        assert_ne!(favicon.data_uri().unwrap(), data_uri);
+
+        fs::remove_file(&path).unwrap();
+        favicon.reload();
+        assert_eq!(favicon.data_uri(), None);
+    }
+}
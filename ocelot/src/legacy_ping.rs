@@ -0,0 +1,239 @@
+use std::{io, time::Duration};
+
+use ocelot_protocol::packet::types::StatusResponse;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::timeout,
+};
+
+// see https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping#1.6 for specs
+
+pub const LEGACY_PING_ID: u8 = 0xFE;
+const LEGACY_KICK_ID: u8 = 0xFF;
+const LEGACY_PLUGIN_MESSAGE_ID: u8 = 0xFA;
+const PING_HOST_CHANNEL: &str = "MC|PingHost";
+/// Protocol version reported to legacy clients, always making them show the version name.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+/// How long to wait for the next byte of a ping before treating it as complete.
+///
+/// Older clients send a prefix of the newer pings and then wait for the answer.
+const FOLLOW_UP_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long the MC|PingHost plugin message of 1.6 may take to arrive.
+const PING_HOST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq)]
+enum LegacyPing {
+    /// Beta 1.8 to 1.3, a lone 0xFE.
+    Beta,
+    /// 1.4 and 1.5, 0xFE 0x01.
+    V1_4,
+    /// 1.6, 0xFE 0x01 followed by an MC|PingHost plugin message.
+    V1_6,
+}
+
+/// Answers a legacy server list ping whose first byte has only been peeked so far.
+pub async fn handle_legacy_ping<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    status: &StatusResponse,
+) -> io::Result<()> {
+    let ping = read_ping(stream).await?;
+    println!("[Client -> Server] Legacy Ping ({:?})", ping);
+    stream.write_all(&legacy_response(&ping, status)).await?;
+    stream.flush().await
+}
+
+/// Reads the ping a byte at a time, as it may arrive split across several reads.
+async fn read_ping<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<LegacyPing> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid legacy ping!");
+    if reader.read_u8().await? != LEGACY_PING_ID {
+        return Err(invalid());
+    }
+    match read_follow_up(reader).await? {
+        None => return Ok(LegacyPing::Beta),
+        Some(0x01) => {}
+        Some(_) => return Err(invalid()),
+    }
+    match read_follow_up(reader).await? {
+        None => Ok(LegacyPing::V1_4),
+        Some(LEGACY_PLUGIN_MESSAGE_ID) => {
+            timeout(PING_HOST_TIMEOUT, read_ping_host(reader))
+                .await
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+            Ok(LegacyPing::V1_6)
+        }
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// Returns `None` if the client sent nothing more within [`FOLLOW_UP_TIMEOUT`].
+async fn read_follow_up<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<u8>> {
+    match timeout(FOLLOW_UP_TIMEOUT, reader.read_u8()).await {
+        Ok(Ok(byte)) => Ok(Some(byte)),
+        Ok(Err(error)) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Ok(Err(error)) => Err(error),
+        Err(_) => Ok(None),
+    }
+}
+
+async fn read_ping_host<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<()> {
+    let channel_length = reader.read_u16().await? as usize;
+    let mut channel = vec![0u16; channel_length];
+    for char in channel.iter_mut() {
+        *char = reader.read_u16().await?;
+    }
+    if String::from_utf16_lossy(&channel) != PING_HOST_CHANNEL {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid legacy ping channel!",
+        ));
+    }
+    let mut data = vec![0u8; reader.read_u16().await? as usize];
+    reader.read_exact(&mut data).await?;
+    Ok(())
+}
+
+fn legacy_response(ping: &LegacyPing, status: &StatusResponse) -> Vec<u8> {
+    let motd = status
+        .description
+        .as_ref()
+        .map(|description| description.plain_text())
+        .unwrap_or_default();
+    let (online, max) = status
+        .players
+        .as_ref()
+        .map(|players| (players.online, players.max))
+        .unwrap_or_default();
+    let message = match ping {
+        // The beta format uses § as separator, so it can't be part of the MOTD.
+        LegacyPing::Beta => format!("{}§{}§{}", motd.replace('§', ""), online, max),
+        LegacyPing::V1_4 | LegacyPing::V1_6 => format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            LEGACY_PROTOCOL_VERSION, status.version.name, motd, online, max
+        ),
+    };
+    let message: Vec<u16> = message.encode_utf16().collect();
+    let mut response = Vec::with_capacity(3 + message.len() * 2);
+    response.push(LEGACY_KICK_ID);
+    response.extend_from_slice(&(message.len() as u16).to_be_bytes());
+    message
+        .iter()
+        .for_each(|char| response.extend_from_slice(&char.to_be_bytes()));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use ocelot_protocol::packet::types::{StatusResponsePlayers, StatusResponseVersion};
+    use ocelot_types::{VarInt, text::TextComponent};
+
+    use super::*;
+
+    fn status() -> StatusResponse {
+        StatusResponse {
+            version: StatusResponseVersion {
+                name: "1.21.11".into(),
+                protocol: VarInt(774),
+            },
+            players: Some(StatusResponsePlayers {
+                max: 20,
+                online: 3,
+                sample: None,
+            }),
+            description: Some(TextComponent::text("A §cMinecraft Server")),
+            favicon: None,
+            enforces_secure_chat: false,
+        }
+    }
+
+    fn utf16_be(string: &str) -> Vec<u8> {
+        string
+            .encode_utf16()
+            .flat_map(|char| char.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn beta_response() {
+        let response = legacy_response(&LegacyPing::Beta, &status());
+        let message = "A cMinecraft Server§3§20";
+        assert_eq!(response[0], LEGACY_KICK_ID);
+        assert_eq!(
+            response[1..3],
+            (message.encode_utf16().count() as u16).to_be_bytes()
+        );
+        assert_eq!(response[3..], utf16_be(message));
+    }
+
+    #[test]
+    fn v1_4_response() {
+        let response = legacy_response(&LegacyPing::V1_4, &status());
+        let message = ["§1", "127", "1.21.11", "A §cMinecraft Server", "3", "20"].join("\0");
+        assert_eq!(response[0], LEGACY_KICK_ID);
+        assert_eq!(
+            response[1..3],
+            (message.encode_utf16().count() as u16).to_be_bytes()
+        );
+        assert_eq!(response[3..], utf16_be(&message));
+    }
+
+    /// An MC|PingHost ping as sent by a 1.6.4 client.
+    fn ping_host(hostname: &str, port: i32) -> Vec<u8> {
+        let mut data = vec![78];
+        data.extend((hostname.encode_utf16().count() as u16).to_be_bytes());
+        data.extend(utf16_be(hostname));
+        data.extend(port.to_be_bytes());
+        let mut ping = vec![LEGACY_PING_ID, 0x01, LEGACY_PLUGIN_MESSAGE_ID];
+        ping.extend((PING_HOST_CHANNEL.encode_utf16().count() as u16).to_be_bytes());
+        ping.extend(utf16_be(PING_HOST_CHANNEL));
+        ping.extend((data.len() as u16).to_be_bytes());
+        ping.extend(data);
+        ping
+    }
+
+    #[tokio::test]
+    async fn ping_versions() {
+        let ping = read_ping(&mut &[LEGACY_PING_ID][..]).await.unwrap();
+        assert_eq!(ping, LegacyPing::Beta);
+        let ping = read_ping(&mut &[LEGACY_PING_ID, 0x01][..]).await.unwrap();
+        assert_eq!(ping, LegacyPing::V1_4);
+        let data = ping_host("localhost", 25565);
+        let ping = read_ping(&mut &data[..]).await.unwrap();
+        assert_eq!(ping, LegacyPing::V1_6);
+
+        assert!(read_ping(&mut &[LEGACY_PING_ID, 0x02][..]).await.is_err());
+        let mut data = ping_host("localhost", 25565);
+        data[6] = b'X';
+        assert!(read_ping(&mut &data[..]).await.is_err());
+        // Cut off within the plugin message
+        assert!(read_ping(&mut &data[..10]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn split_ping_host() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let handler = tokio::spawn(async move { handle_legacy_ping(&mut server, &status()).await });
+
+        let data = ping_host("localhost", 25565);
+        for chunk in [&data[..1], &data[1..3], &data[3..20], &data[20..]] {
+            client.write_all(chunk).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        handler.await.unwrap().unwrap();
+        assert_eq!(response, legacy_response(&LegacyPing::V1_6, &status()));
+    }
+
+    #[tokio::test]
+    async fn beta_ping_waits_for_more() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let handler = tokio::spawn(async move { handle_legacy_ping(&mut server, &status()).await });
+
+        // Beta clients send a lone 0xFE and keep the connection open
+        client.write_all(&[LEGACY_PING_ID]).await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        handler.await.unwrap().unwrap();
+        assert_eq!(response, legacy_response(&LegacyPing::Beta, &status()));
+    }
+}
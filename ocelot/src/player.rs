@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use openssl::hash::{MessageDigest, hash};
+use rand::seq::IteratorRandom;
+use uuid::{Builder, Uuid};
+
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 16;
+
+/// Same as vanilla's `UUID.nameUUIDFromBytes("OfflinePlayer:" + name)`.
+pub fn offline_uuid(username: &str) -> Uuid {
+    let digest = hash(
+        MessageDigest::md5(),
+        format!("OfflinePlayer:{}", username).as_bytes(),
+    )
+    .expect("MD5 is always available");
+    Builder::from_md5_bytes((*digest).try_into().unwrap()).into_uuid()
+}
+
+pub fn is_valid_username(username: &str) -> bool {
+    (MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&username.len())
+        && username
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+pub struct Player {
+    pub username: Option<String>,
+    pub uuid: Option<Uuid>,
+}
+
+struct OnlinePlayer {
+    username: String,
+    // ID of the connection that registered the player.
+    connection: u64,
+}
+
+#[derive(Default)]
+pub struct PlayerRegistry {
+    players: RwLock<HashMap<Uuid, OnlinePlayer>>,
+}
+impl PlayerRegistry {
+    /// Replaces a player with the same UUID that's still registered by an older connection.
+    pub fn add(&self, uuid: Uuid, username: impl Into<String>, connection: u64) {
+        let player = OnlinePlayer {
+            username: username.into(),
+            connection,
+        };
+        self.players.write().unwrap().insert(uuid, player);
+    }
+    /// Only removes the player if it still belongs to `connection`, so ending an old session
+    /// doesn't remove the player that reconnected in the meantime.
+    pub fn remove(&self, uuid: &Uuid, connection: u64) {
+        let mut players = self.players.write().unwrap();
+        if players
+            .get(uuid)
+            .is_some_and(|player| player.connection == connection)
+        {
+            players.remove(uuid);
+        }
+    }
+    pub fn count(&self) -> usize {
+        self.players.read().unwrap().len()
+    }
+    /// Picks up to `limit` random online players, like the vanilla server list sample.
+    pub fn sample(&self, limit: usize) -> Vec<(Uuid, String)> {
+        self.players
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(uuid, player)| (*uuid, player.username.clone()))
+            .choose_multiple(&mut rand::rng(), limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuids() {
+        assert_eq!(
+            offline_uuid("Notch"),
+            Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap()
+        );
+        assert_eq!(
+            offline_uuid("jeb_"),
+            Uuid::parse_str("a762f560-4fce-3236-812a-b80efff0b62b").unwrap()
+        );
+        assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+    }
+
+    #[test]
+    fn usernames() {
+        assert!(is_valid_username("Notch"));
+        assert!(is_valid_username("jeb_"));
+        assert!(is_valid_username("abc"));
+        assert!(is_valid_username("A_very_long_name"));
+        assert!(!is_valid_username("ab"));
+        assert!(!is_valid_username("A_very_long_name1"));
+        assert!(!is_valid_username("Not ch"));
+        assert!(!is_valid_username("Nötch"));
+        assert!(!is_valid_username("Notch§c"));
+    }
+
+    #[test]
+    fn reconnect() {
+        let registry = PlayerRegistry::default();
+        let uuid = offline_uuid("Notch");
+        registry.add(uuid, "Notch", 1);
+        registry.add(uuid, "Notch", 2);
+        registry.remove(&uuid, 1);
+        assert_eq!(registry.sample(1), vec![(uuid, "Notch".to_string())]);
+        registry.remove(&uuid, 2);
+        assert_eq!(registry.count(), 0);
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use openssl::{pkey::Private, rsa::Rsa};
+
+use crate::{
+    player::PlayerRegistry,
+    session::{MOJANG_SESSION_SERVER, SessionService},
+    status::StatusProvider,
+};
+
+pub struct ServerConfig {
+    /// Packets of at least this many bytes are compressed, `None` disables compression.
+    pub compression_threshold: Option<usize>,
+    /// Authenticates players against the session server and encrypts the connection.
+    pub online_mode: bool,
+    pub session_server: String,
+    /// Only warns about packets with trailing bytes instead of disconnecting the client.
+    pub lenient_decoding: bool,
+}
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            compression_threshold: Some(256),
+            online_mode: true,
+            session_server: MOJANG_SESSION_SERVER.into(),
+            lenient_decoding: false,
+        }
+    }
+}
+
+pub struct Server {
+    pub config: ServerConfig,
+    pub rsa_key_pair: Rsa<Private>,
+    pub players: Arc<PlayerRegistry>,
+    pub status_provider: Box<dyn StatusProvider>,
+    pub session_service: Box<dyn SessionService>,
+}
@@ -0,0 +1,196 @@
+use std::io;
+
+use async_trait::async_trait;
+use num_bigint::BigInt;
+use ocelot_protocol::packet::types::Properties;
+use ocelot_types::BoundedString;
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub const MOJANG_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+pub fn get_server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut hasher = openssl::sha::Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key_der);
+    let hash_result = hasher.finish();
+    let big_int = BigInt::from_signed_bytes_be(&hash_result);
+    format!("{:x}", big_int)
+}
+
+#[derive(Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+#[derive(Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+impl ProfileProperty {
+    pub fn to_properties(&self) -> io::Result<Properties> {
+        Ok(Properties {
+            name: BoundedString::new(&self.name)?,
+            value: BoundedString::new(&self.value)?,
+            signature: self
+                .signature
+                .as_ref()
+                .map(BoundedString::new)
+                .transpose()?,
+        })
+    }
+}
+
+#[async_trait]
+pub trait SessionService: Send + Sync {
+    /// Returns the authenticated profile, or `None` if the player did not join with this server hash.
+    async fn has_joined(
+        &self,
+        username: &str,
+        server_hash: &str,
+    ) -> io::Result<Option<GameProfile>>;
+}
+
+/// Session service speaking the Mojang session server API, at any base URL.
+pub struct HttpSessionService {
+    client: reqwest::Client,
+    base_url: String,
+}
+impl HttpSessionService {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into(),
+        }
+    }
+}
+#[async_trait]
+impl SessionService for HttpSessionService {
+    async fn has_joined(
+        &self,
+        username: &str,
+        server_hash: &str,
+    ) -> io::Result<Option<GameProfile>> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/session/minecraft/hasJoined",
+                self.base_url.trim_end_matches('/')
+            ))
+            .query(&[("username", username), ("serverId", server_hash)])
+            .send()
+            .await
+            .map_err(io::Error::other)?;
+        match response.status().as_u16() {
+            200 => {
+                let body = response.text().await.map_err(io::Error::other)?;
+                let profile = serde_json::from_str(&body)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                Ok(Some(profile))
+            }
+            204 => Ok(None),
+            status => Err(io::Error::other(format!(
+                "Session server responded with status {}",
+                status
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    const SERVER_HASH: &str = "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1";
+
+    /// Answers a single `hasJoined` request like the session server would and returns the request line.
+    async fn has_joined_with_response(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, io::Result<Option<GameProfile>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let service = HttpSessionService::new(format!("http://{}", listener.local_addr().unwrap()));
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let read = socket.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..read]).into_owned();
+            request.lines().next().unwrap_or_default().to_string()
+        });
+        let profile = service.has_joined("Notch", SERVER_HASH).await;
+        (server.await.unwrap(), profile)
+    }
+
+    #[test]
+    fn server_hash() {
+        // see https://minecraft.wiki/w/Protocol_encryption#Sample_code for the expected digests
+        assert_eq!(
+            get_server_hash("Notch", &[], &[]),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(get_server_hash("jeb_", &[], &[]), SERVER_HASH);
+        assert_eq!(
+            get_server_hash("simon", &[], &[]),
+            "88e16a1019277b15d58faf0541e11910eb756f6"
+        );
+        // The server ID, shared secret and public key are hashed in that order.
+        assert_eq!(
+            get_server_hash("si", b"m", b"on"),
+            get_server_hash("simon", &[], &[])
+        );
+        assert_ne!(
+            get_server_hash("si", b"on", b"m"),
+            get_server_hash("simon", &[], &[])
+        );
+    }
+
+    #[tokio::test]
+    async fn has_joined() {
+        let (request, profile) = has_joined_with_response(
+            "200 OK",
+            r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"e30=","signature":"c2ln"}]}"#,
+        )
+        .await;
+        assert_eq!(
+            request,
+            format!(
+                "GET /session/minecraft/hasJoined?username=Notch&serverId={} HTTP/1.1",
+                SERVER_HASH
+            )
+        );
+        let profile = profile.unwrap().unwrap();
+        assert_eq!(
+            profile.id,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(profile.name, "Notch");
+        assert_eq!(profile.properties.len(), 1);
+        assert_eq!(profile.properties[0].name, "textures");
+        assert_eq!(profile.properties[0].value, "e30=");
+        assert_eq!(profile.properties[0].signature.as_deref(), Some("c2ln"));
+    }
+
+    #[tokio::test]
+    async fn has_not_joined() {
+        let (_, profile) = has_joined_with_response("204 No Content", "").await;
+        assert!(profile.unwrap().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use ocelot_protocol::{
+    MINECRAFT_VERSION, PROTOCOL_VERSION,
+    packet::types::{
+        StatusResponse, StatusResponsePlayer, StatusResponsePlayers, StatusResponseVersion,
+    },
+};
+use ocelot_types::{MAX_STRING_LENGTH, VarInt, text::TextComponent};
+
+use crate::{favicon::Favicon, player::PlayerRegistry};
+
+pub const SAMPLE_SIZE: usize = 12;
+
+pub trait StatusProvider: Send + Sync {
+    fn status(&self) -> StatusResponse;
+}
+
+pub struct DefaultStatusProvider {
+    players: Arc<PlayerRegistry>,
+    pub motd: String,
+    pub max_players: i32,
+    pub enforces_secure_chat: bool,
+    pub favicon: Option<Arc<Favicon>>,
+}
+impl DefaultStatusProvider {
+    pub fn new(players: Arc<PlayerRegistry>) -> Self {
+        Self {
+            players,
+            motd: "A Minecraft Server".into(),
+            max_players: 20,
+            enforces_secure_chat: false,
+            favicon: Some(Favicon::watch("server-icon.png")),
+        }
+    }
+}
+impl StatusProvider for DefaultStatusProvider {
+    fn status(&self) -> StatusResponse {
+        let sample = self
+            .players
+            .sample(SAMPLE_SIZE)
+            .into_iter()
+            .map(|(id, name)| StatusResponsePlayer { name, id })
+            .collect();
+        StatusResponse {
+            version: StatusResponseVersion {
+                name: MINECRAFT_VERSION.into(),
+                protocol: VarInt(PROTOCOL_VERSION),
+            },
+            players: Some(StatusResponsePlayers {
+                max: self.max_players,
+                online: self.players.count() as i32,
+                sample: Some(sample),
+            }),
+            description: Some(TextComponent::text(&self.motd)),
+            favicon: self.favicon.as_deref().and_then(Favicon::data_uri),
+            enforces_secure_chat: self.enforces_secure_chat,
+        }
+    }
+}
+
+/// Drops the favicon if the serialized response would not fit into the status packet's string.
+pub fn limit_response_size(status: &mut StatusResponse) {
+    if status.favicon.is_none() {
+        return;
+    }
+    let length = serde_json::to_string(status)
+        .map(|json| json.encode_utf16().count() as u64)
+        .unwrap_or(u64::MAX);
+    if length > MAX_STRING_LENGTH {
+        eprintln!(
+            "Status response is too long ({} > {}), leaving out the favicon",
+            length, MAX_STRING_LENGTH
+        );
+        status.favicon = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(favicon: String) -> StatusResponse {
+        StatusResponse {
+            version: StatusResponseVersion {
+                name: MINECRAFT_VERSION.into(),
+                protocol: VarInt(PROTOCOL_VERSION),
+            },
+            players: None,
+            description: Some(TextComponent::text("A Minecraft Server")),
+            favicon: Some(favicon),
+            enforces_secure_chat: false,
+        }
+    }
+
+    #[test]
+    fn response_size_limit() {
+        let mut small = status("data:image/png;base64,AAAA".into());
+        limit_response_size(&mut small);
+        assert!(small.favicon.is_some());
+
+        let mut large = status("A".repeat(MAX_STRING_LENGTH as usize));
+        limit_response_size(&mut large);
+        assert!(large.favicon.is_none());
+        assert!(serde_json::to_string(&large).unwrap().len() as u64 <= MAX_STRING_LENGTH);
+    }
+}